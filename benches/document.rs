@@ -0,0 +1,66 @@
+//! Measures the cost of a keystroke on a large manuscript, both for the
+//! incremental `Document` and for re-reading the whole text like the editor
+//! used to. Run with `cargo bench --bench document`.
+
+#[allow(dead_code)]
+#[path = "../src/document.rs"]
+mod document;
+
+#[allow(dead_code)]
+#[path = "../src/wordcount.rs"]
+mod wordcount;
+
+use std::time::{Duration, Instant};
+
+use document::Document;
+use wordcount::{
+	CountingRules, count_words,
+};
+
+const WORDS: usize = 500_000;
+const KEYSTROKES: u32 = 200;
+
+fn manuscript() -> String {
+	let mut text = String::new();
+	let mut words = 0;
+	let mut chapter = 0;
+	while words < WORDS {
+		chapter += 1;
+		text.push_str(&format!("# Chapter {}\n\n", chapter));
+		for _ in 0..100 {
+			text.push_str("It was a bright cold day in April, and the clocks were striking thirteen.\n");
+			words += 14;
+		}
+		text.push('\n');
+	}
+	text
+}
+
+fn report(name: &str, elapsed: Duration) {
+	println!("{:<32} {:>10.3?} per keystroke", name, elapsed / KEYSTROKES);
+}
+
+fn main() {
+	let text = manuscript();
+	let line = text.lines().count() / 2;
+
+	let mut document = Document::new(&text, CountingRules::default());
+	let start = Instant::now();
+	for i in 0..KEYSTROKES {
+		document.splice(line, 1, vec![format!("A new line being typed {}", i)]);
+	}
+	report("incremental splice", start.elapsed());
+
+	let start = Instant::now();
+	for _ in 0..KEYSTROKES {
+		std::hint::black_box(document.sections());
+	}
+	report("sections (debounced, worker)", start.elapsed());
+
+	let start = Instant::now();
+	for _ in 0..KEYSTROKES {
+		let copy = text.clone();
+		std::hint::black_box(count_words(copy.as_str(), &CountingRules::default()));
+	}
+	report("whole-buffer recount", start.elapsed());
+}
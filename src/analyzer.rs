@@ -0,0 +1,81 @@
+use relm4::{
+	ComponentSender, Worker,
+};
+
+use crate::{
+	document::Document,
+	frequency::FrequencyReport,
+	preview,
+	rhythm::{
+		Paragraph, paragraphs,
+	},
+	wordcount::CountingRules,
+};
+
+/// Runs the analyses that are too heavy for every keystroke on a separate
+/// thread. It keeps its own mirror of the document, fed with the same line
+/// edits as the editor, so only the changed lines cross threads.
+pub(super) struct Analyzer {
+	document: Document,
+}
+
+#[derive(Debug)]
+pub(super) enum AnalyzerMsg {
+	Splice {
+		start: usize,
+		removed: usize,
+		lines: Vec<String>,
+	},
+	/// Analyzes the document as of the given generation of edits.
+	Analyze(usize),
+	Frequencies,
+	Rhythm,
+	Preview,
+	SetRules(CountingRules),
+}
+
+#[derive(Debug)]
+pub(super) enum AnalyzerOutput {
+	Sections(usize, Vec<(String, usize)>),
+	Frequencies(FrequencyReport),
+	Rhythm(Vec<Paragraph>),
+	/// The document rendered as HTML.
+	Preview(String),
+}
+
+impl Worker for Analyzer {
+	type Init = CountingRules;
+	type Input = AnalyzerMsg;
+	type Output = AnalyzerOutput;
+
+	fn init(rules: Self::Init, _sender: ComponentSender<Self>) -> Self {
+		Self {
+			document: Document::with_rules(rules),
+		}
+	}
+
+	fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
+		match message {
+			AnalyzerMsg::Splice { start, removed, lines } => {
+				self.document.splice(start, removed, lines);
+			},
+			AnalyzerMsg::Analyze(generation) => {
+				sender.output(AnalyzerOutput::Sections(generation, self.document.sections())).unwrap();
+			},
+			AnalyzerMsg::Frequencies => {
+				// Front matter and comments are not part of the prose.
+				let prose: Vec<&str> = self.document.prose_lines().map(|(_, line)| line).collect();
+				sender.output(AnalyzerOutput::Frequencies(FrequencyReport::compute(&prose.join("\n")))).unwrap();
+			},
+			AnalyzerMsg::Rhythm => {
+				sender.output(AnalyzerOutput::Rhythm(paragraphs(&self.document))).unwrap();
+			},
+			AnalyzerMsg::Preview => {
+				sender.output(AnalyzerOutput::Preview(preview::render(&self.document))).unwrap();
+			},
+			AnalyzerMsg::SetRules(rules) => {
+				self.document.set_rules(rules);
+			},
+		}
+	}
+}
@@ -1,7 +1,8 @@
 use std::{
-	io::prelude::*,
-	path, path::Path,
-	fs, fs::File,
+	cell::Cell,
+	collections::HashMap,
+	rc::Rc,
+	time::Duration,
 };
 
 use tracker::track;
@@ -11,14 +12,67 @@ use relm4::{
     factory::{FactoryVecDeque},
     gtk, gtk::prelude::*, gtk::{gio, glib},
     Component, ComponentParts, ComponentSender, Controller, main_application, SimpleComponent,
+    WorkerController,
 };
 
 use crate::{
+	analyzer::{
+		Analyzer, AnalyzerMsg, AnalyzerOutput,
+	},
+	binder::{
+		BinderRow, BinderRowOutput, ProjectRow, ProjectRowOutput, WritingRow, WritingRowOutput,
+	},
 	config::{APP_ID},
-	i18n::i18n,
+	continuation,
+	corkboard::{
+		Card, CardOutput,
+	},
+	document::{
+		Document, split_lines,
+	},
+	focus::{
+		self, FocusScope,
+	},
+	formatting::{
+		Format, formatting_panel,
+	},
+	frequency::FrequencyReport,
+	goals::{
+		GoalProgress, goals_page,
+	},
+	history::{
+		History, HEATMAP_WEEKS, draw_heatmap,
+	},
+	i18n::{
+		i18n, ni18n_f,
+	},
+	preview,
+	punctuation::{
+		self, Locale,
+	},
+	rhythm::{
+		self, Paragraph,
+	},
+	sprint::{
+		Sprint, summary,
+	},
+	templates::Template,
+	stats::{
+		StatScope, TextStats, stats_view_stack,
+	},
+	store::{
+		self, BinderItem, BinderMove, DocumentKind, Goal, Project, ProjectTemplate, Writing, WritingMetadata,
+	},
 	toc::{
-		Section
-	}
+		Outline, OutlineChange, Section, SectionId, SectionOutput, SectionStatus, draw_progress_ring,
+	},
+	theme,
+	typewriter::Typewriter,
+	typography::{
+		self, Typography,
+	},
+	wordcount::CountingRules,
+	wysiwym,
 };
 
 use sourceview5::prelude::*;
@@ -31,101 +85,1286 @@ pub(super) enum WindowPage {
 	Editor,
 }
 
+#[derive(Debug, PartialEq)]
+pub(super) enum EditorMode {
+	Text,
+	Corkboard,
+}
+
 #[derive(Debug, PartialEq)]
 pub(super) enum SidebarPage {
+	Binder,
 	Sections,
 	Formatting,
 	SpellCheck,
 	FindReplace,
 	History,
+	Analysis,
 }
 
 #[tracker::track]
 pub(super) struct App {
 	visible_sidebar_page: SidebarPage,
 	visible_window_page: WindowPage,
+	editor_mode: EditorMode,
 	is_stat_dialog_visible: bool,
 	is_page_empty: bool,
 	word_count: usize,
 	is_dark: bool,
 	ideas: Vec<String>,
 	title: String,
-	text: String,
+	/// Words written on each of the days shown in the heatmap, oldest first.
+	activity: Vec<usize>,
+	streak: usize,
+	/// Progress towards the goal, shown on the word count.
+	goal_progress: GoalProgress,
+	sprint: Option<Sprint>,
+	is_sprinting: bool,
+	is_previewing: bool,
+	/// Whether all but the text around the cursor is dimmed and hidden away.
+	is_focused: bool,
+	typography: Typography,
+	/// Id of the style scheme of the text, for the theme and the light or dark style.
+	style_scheme: String,
+	#[tracker::do_not_track]
+	document: Document,
+	#[tracker::do_not_track]
+	analyzer: WorkerController<Analyzer>,
+	/// Counts the edits, so that analyses of text since edited are dropped.
+	#[tracker::do_not_track]
+	analysis_generation: usize,
 	#[tracker::do_not_track]
 	headings: FactoryVecDeque<Section>,
-	headings_created: u8,
+	#[tracker::do_not_track]
+	cards: FactoryVecDeque<Card>,
+	#[tracker::do_not_track]
+	outline: Outline,
+	#[tracker::do_not_track]
+	title_buffer: sourceview5::Buffer,
+	#[tracker::do_not_track]
+	buffer: sourceview5::Buffer,
+	#[tracker::do_not_track]
+	text_view: sourceview5::View,
+	section_filter: Option<SectionStatus>,
+	#[tracker::do_not_track]
+	writing_id: String,
+	/// When the writing was last edited, see `store::now`.
+	#[tracker::do_not_track]
+	writing_modified: i64,
+	#[tracker::do_not_track]
+	metadata: WritingMetadata,
+	#[tracker::do_not_track]
+	stats: Vec<(StatScope, TextStats)>,
+	#[tracker::do_not_track]
+	project: Option<Project>,
+	#[tracker::do_not_track]
+	open_document: Option<String>,
+	/// Word counts of the documents of the project, by id, so that the total
+	/// only recounts the open one.
+	#[tracker::do_not_track]
+	word_counts: HashMap<String, usize>,
+	#[tracker::do_not_track]
+	binder: FactoryVecDeque<BinderRow>,
+	#[tracker::do_not_track]
+	projects: FactoryVecDeque<ProjectRow>,
+	#[tracker::do_not_track]
+	writings: FactoryVecDeque<WritingRow>,
+	#[tracker::do_not_track]
+	history: History,
+	#[tracker::do_not_track]
+	history_view: webkit6::WebView,
+	#[tracker::do_not_track]
+	analysis_view: webkit6::WebView,
+	#[tracker::do_not_track]
+	preview_view: webkit6::WebView,
+	/// Scrolls the text view, followed by the preview.
+	#[tracker::do_not_track]
+	text_scroller: gtk::ScrolledWindow,
+	/// The word or phrase of the Analysis page shown in the text.
+	#[tracker::do_not_track]
+	highlighted_term: Option<String>,
+	#[tracker::do_not_track]
+	sprint_generation: usize,
+	#[tracker::do_not_track]
+	counting_rules: CountingRules,
+	/// Kept around to hear about changes to the settings.
+	#[tracker::do_not_track]
+	settings: gio::Settings,
+	/// Whether Markdown is hidden outside the line being edited.
+	#[tracker::do_not_track]
+	hides_markup: bool,
+	#[tracker::do_not_track]
+	cursor_line: i32,
+	#[tracker::do_not_track]
+	focus_scope: FocusScope,
+	#[tracker::do_not_track]
+	typewriter: Rc<Typewriter>,
+	/// Applies the accent color, and the font and line height of `typography`
+	/// to the text view.
+	#[tracker::do_not_track]
+	css_provider: gtk::CssProvider,
+	#[tracker::do_not_track]
+	text_style_manager: sourceview5::StyleSchemeManager,
+	/// Set around the edits of the buffer made by the application rather than
+	/// typed, such as moving a section, which the history leaves out.
+	#[tracker::do_not_track]
+	suppress_history: Rc<Cell<bool>>,
 }
 
 #[derive(Debug)]
 pub enum AppMsg {
 	SwitchSidebarPage(SidebarPage),
 	SwitchWindowPage(WindowPage),
+	SwitchEditorMode(EditorMode),
 	ChangeTheme(bool),
-	ChangeText(String),
+	ChangeLines {
+		start: usize,
+		removed: usize,
+		lines: Vec<String>,
+		/// Whether the edit was made by the user, rather than by loading text.
+		typed: bool,
+	},
+	Analyze(usize),
+	MoveCursor(i32),
+	ApplyFormat(Format),
+	/// A character was typed at `offset`, which smart punctuation may replace.
+	Punctuate {
+		offset: i32,
+		typed: char,
+	},
+	ConvertPunctuation,
+	/// Sets the language of the writing, or clears it when empty.
+	ChangeLanguage(String),
+	/// The sections as of the given generation of edits.
+	ChangeSections(usize, Vec<(String, usize)>),
 	ChangeTitle(String),
 	ToggleStatDialog,
+	ChangeSectionStatus(SectionId, SectionStatus),
+	ChangeSectionTarget(SectionId, Option<usize>),
+	ChangeSectionSynopsis(SectionId, String),
+	FilterSections(Option<SectionStatus>),
+	JumpToSection(SectionId),
+	MoveSection {
+		from: SectionId,
+		to: SectionId,
+	},
+	NewProject,
+	OpenProject(String),
+	OpenWriting(String),
+	/// Opens the writing, then asks how to develop it into a project.
+	DevelopWriting(String),
+	OpenDocument(String),
+	CreateDocument(DocumentKind),
+	RenameDocument(String, String),
+	MoveDocument(String, BinderMove),
+	/// Asks whether to delete the document, along with those in it.
+	ConfirmDeleteDocument(String),
+	DeleteDocument(String),
+	CompileProject,
+	SplitSection(SectionId),
+	ChooseMergeTarget,
+	MergeInto(String),
+	ChooseProjectTemplate,
+	DevelopIntoProject(ProjectTemplate),
+	ChooseTemplate,
+	NewWriting(String, Option<Template>),
+	ImportTemplate,
+	ExportTemplate(Template),
+	RefreshHistory,
+	ExportHistory,
+	ChangeGoal(Goal),
+	ChangeSettings,
+	RefreshAnalysis,
+	ChangeFrequencies(FrequencyReport),
+	HighlightTerm(String),
+	ChangeRhythm(Vec<Paragraph>),
+	TogglePreview(bool),
+	RefreshPreview,
+	ShowPreview(String),
+	SyncPreview,
+	ToggleFocusMode,
+	ShowPreferences,
+	/// Sets the style scheme again, after its colors were edited.
+	RefreshStyleScheme,
+	JumpToSentence {
+		line: usize,
+		offset: usize,
+	},
+	ChooseSprint,
+	StartSprint {
+		minutes: u32,
+		target: Option<usize>,
+	},
+	SprintTick(usize),
+	StopSprint,
+	Autosave,
 	Quit,
 }
 
+/// Number of lines shown as a preview of a writing on the Home page.
+const EXCERPT_LINES: usize = 2;
+
+const ANALYSIS_DELAY: Duration = Duration::from_millis(250);
+
+const SPRINT_TICK: Duration = Duration::from_secs(1);
+
+/// How often the writing, or the project, and the history are saved.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Number of days charted on the History page.
+const HISTORY_CHART_DAYS: usize = 30;
+
+/// Replaces the text of `buffer` with that of another document, which undoing
+/// must not bring back.
+fn set_text_irreversibly(buffer: &sourceview5::Buffer, text: &str) {
+	buffer.begin_irreversible_action();
+	buffer.set_text(text);
+	buffer.end_irreversible_action();
+}
+
+fn load_counting_rules(settings: &gio::Settings) -> CountingRules {
+	CountingRules {
+		cjk_characters_as_words: settings.boolean("count-cjk-characters"),
+		hyphenated_as_one: settings.boolean("count-hyphenated-as-one"),
+		count_numbers: settings.boolean("count-numbers"),
+		count_urls: settings.boolean("count-urls"),
+		exclude_markup: settings.boolean("exclude-markup"),
+	}
+}
+
 relm4::new_action_group!(AppActionGroup, "app");
 relm4::new_stateless_action!(QuitAction, AppActionGroup, "quit");
 relm4::new_stateless_action!(FormattingAction, AppActionGroup, "formatting");
 relm4::new_stateless_action!(DeleteAction, AppActionGroup, "delete");
+// Takes the id of a `Format` as its target, and has no state.
+relm4::new_stateful_action!(FormatAction, AppActionGroup, "format", String, ());
+relm4::new_stateless_action!(FocusModeAction, AppActionGroup, "focus-mode");
+relm4::new_stateless_action!(PreferencesAction, AppActionGroup, "preferences");
+relm4::new_stateless_action!(ZoomInAction, AppActionGroup, "zoom-in");
+relm4::new_stateless_action!(ZoomOutAction, AppActionGroup, "zoom-out");
+relm4::new_stateless_action!(ZoomResetAction, AppActionGroup, "zoom-reset");
+relm4::new_stateless_action!(ConvertPunctuationAction, AppActionGroup, "convert-punctuation");
+relm4::new_stateful_action!(LanguageAction, AppActionGroup, "language", String, String);
+// Backed by the settings of the same name, see `gio::Settings::create_action`.
+relm4::new_stateful_action!(HideMarkupAction, AppActionGroup, "hide-markup", (), bool);
+relm4::new_stateful_action!(TypewriterScrollingAction, AppActionGroup, "typewriter-scrolling", (), bool);
+relm4::new_stateful_action!(FocusScopeAction, AppActionGroup, "focus-scope", String, String);
+relm4::new_stateful_action!(SmartPunctuationAction, AppActionGroup, "smart-punctuation", (), bool);
 
-fn strip_headings(src: &str) -> Vec<&str> {
-	lazy_static::lazy_static! {
-		static ref RE: regex::Regex = regex::Regex::new(r"#+ .+").unwrap();
+impl App {
+	fn load_writings() -> Vec<Writing> {
+		Writing::load_all()
 	}
-	RE.find_iter(src)
-		.map(|x| x.as_str())
-		.collect()
-}
 
-struct Writing {
-	id: String,
-}
+	fn current_writing(&self) -> Writing {
+		Writing {
+			id: self.writing_id.clone(),
+			title: self.title.clone(),
+			text: self.document.text(),
+			outline: self.outline.saved(),
+			metadata: self.metadata.clone(),
+			modified: self.writing_modified,
+		}
+	}
 
-impl App {
-	fn load_writings() {
-		let dir = Path::new("/home/kinten/.local/share/austeur/writings");
-		if !dir.is_dir() {
-			return ();
-		};
-		fs::read_dir(dir).unwrap()
-			.map(|res| res.map(|e| e.path()))
-        	.collect::<Result<Vec<_>, std::io::Error>>()
-        	.into_iter()
-        	.map(|paths| paths.into_iter().filter_map(|path| {
-        		let mut file = match File::open(path) {
-        			Ok(val) => val,
-        			Err(..) => {
-        				return None;
-        			},
-        		};
-        		let mut contents = String::new();
-        		let result = match file.read_to_string(&mut contents) {
-        			Ok(..) => true,
-        			Err(..) => {
-        				return None;
-        			},
-        		};
-        		let contents_str = contents.as_str();
-        		let parsed = match json::parse(contents_str) {
-        			Ok(val) => val,
-        			Err(..) => {
-        				return None;
-        			},
-        		};
-        		println!("parsed: {}", parsed);
-        		Some(parsed)
-        	}).collect::<Vec<_>>())
-        	.collect::<Vec<_>>();
+	fn save(&mut self) -> std::io::Result<()> {
+		self.sync_outline();
+		self.sync_open_document();
+		self.history.save()?;
+		match &mut self.project {
+			Some(project) => {
+				project.metadata = self.metadata.clone();
+				project.save()
+			},
+			None => {
+				let writing = self.current_writing();
+				if !writing.is_empty() {
+					return writing.save();
+				}
+				// A writing left empty is not kept around.
+				match Writing::delete(&writing.id) {
+					Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+					_ => Ok(()),
+				}
+			},
+		}
+	}
+
+	/// Runs an edit of the buffer made by the application rather than typed,
+	/// which the history leaves out.
+	fn edit_quietly(&self, edit: impl FnOnce(&gtk::TextBuffer)) {
+		self.suppress_history.set(true);
+		edit(self.buffer.upcast_ref());
+		self.suppress_history.set(false);
+	}
+
+	/// The id under which the words written are recorded: the project's, if
+	/// one is open, otherwise the writing's.
+	fn history_id(&self) -> String {
+		match &self.project {
+			Some(project) => project.id.clone(),
+			None => self.writing_id.clone(),
+		}
+	}
+
+	fn refresh_activity(&mut self) {
+		let activity = self.history.recent_days(HEATMAP_WEEKS * 7).into_iter()
+			.map(|(_, record)| record.written)
+			.collect();
+		self.set_activity(activity);
+		self.set_streak(self.history.streak());
+	}
+
+	fn refresh_history_chart(&self) {
+		let script = format!("window.austeur.setHistory({})", self.history.to_chart_json(HISTORY_CHART_DAYS));
+		self.history_view.evaluate_javascript(&script, None, None, None::<&gio::Cancellable>, |result| {
+			if let Err(err) = result {
+				eprintln!("Could not chart history: {}", err);
+			}
+		});
+	}
+
+	/// Words in the whole writing, or in the manuscript of the open project.
+	fn total_word_count(&self) -> usize {
+		let Some(project) = &self.project else {
+			return self.document.word_count();
+		};
+		project.manuscript().into_iter()
+			.map(|item| match &self.open_document {
+				Some(open) if *open == item.id => self.document.word_count(),
+				_ => self.word_counts.get(&item.id).copied().unwrap_or_default(),
+			})
+			.sum()
+	}
+
+	/// Counts the words of every document of the project anew.
+	fn count_project_words(&mut self) {
+		let Some(project) = &self.project else {
+			return;
+		};
+		self.word_counts = project.flatten().into_iter()
+			.map(|(_, item)| (item.id.clone(), Document::new(&item.text, self.counting_rules).word_count()))
+			.collect();
+	}
+
+	fn refresh_goal(&mut self) {
+		let id = self.history_id();
+		let words = self.total_word_count();
+		let progress = GoalProgress::new(self.metadata.goal.clone(), words, self.history.written_today(&id), &self.history, &id);
+		if progress.is_daily_goal_met() && !self.history.is_goal_notified_today() {
+			self.history.set_goal_notified_today();
+			if let Err(err) = self.history.save() {
+				eprintln!("Could not save history: {}", err);
+			}
+			let notification = gio::Notification::new(&i18n("Daily Goal Met"));
+			notification.set_body(Some(&ni18n_f("{count} word written today. Well done!", "{count} words written today. Well done!", progress.written_today, &[("count", &progress.written_today.to_string())])));
+			main_application().send_notification(Some("daily-goal"), &notification);
+		}
+		self.set_goal_progress(progress);
+	}
+
+	fn choose_sprint(&mut self, sender: ComponentSender<Self>) {
+		let minutes_row = adw::SpinRow::with_range(1.0, 180.0, 5.0);
+		minutes_row.set_title(&i18n("Duration"));
+		minutes_row.set_subtitle(&i18n("Minutes"));
+		minutes_row.set_value(15.0);
+		let target_row = adw::SpinRow::with_range(0.0, 10_000.0, 50.0);
+		target_row.set_title(&i18n("Word Target"));
+		target_row.set_subtitle(&i18n("Words, 0 for none"));
+		let list = gtk::ListBox::builder()
+			.css_classes(["boxed-list"])
+			.selection_mode(gtk::SelectionMode::None)
+			.build();
+		list.append(&minutes_row);
+		list.append(&target_row);
+
+		let dialog = adw::AlertDialog::new(
+			Some(&i18n("Writing Sprint")),
+			Some(&i18n("Write as much as you can until the time runs out")),
+		);
+		dialog.set_extra_child(Some(&list));
+		dialog.add_response("cancel", &i18n("_Cancel"));
+		dialog.add_response("start", &i18n("_Start"));
+		dialog.set_response_appearance("start", adw::ResponseAppearance::Suggested);
+		dialog.set_default_response(Some("start"));
+		dialog.set_close_response("cancel");
+		dialog.connect_response(None, move |_, response| {
+			if response == "start" {
+				sender.input(AppMsg::StartSprint {
+					minutes: minutes_row.value() as u32,
+					target: Some(target_row.value() as usize).filter(|target| *target > 0),
+				});
+			}
+		});
+		if let Some(window) = main_application().active_window() {
+			dialog.present(&window);
+		}
+	}
+
+	fn start_sprint(&mut self, minutes: u32, target: Option<usize>, sender: ComponentSender<Self>) {
+		self.set_sprint(Some(Sprint::new(minutes, target, self.total_word_count())));
+		self.set_is_sprinting(true);
+		self.set_editor_mode(EditorMode::Text);
+		self.sprint_generation += 1;
+		let generation = self.sprint_generation;
+		glib::timeout_add_local_once(SPRINT_TICK, move || {
+			sender.input(AppMsg::SprintTick(generation));
+		});
+		self.text_view.grab_focus();
+	}
+
+	/// Stores the sprint in the history and sums it up.
+	fn finish_sprint(&mut self) {
+		// Ticks still on their way belong to the sprint that just ended.
+		self.sprint_generation += 1;
+		self.set_is_sprinting(false);
+		let word_count = self.total_word_count();
+		let Some(mut sprint) = self.get_mut_sprint().take() else {
+			return;
+		};
+		// Stopped early, the sprint lasted until now.
+		sprint.tick(word_count);
+		let record = sprint.record(self.history_id());
+		let body = summary(&record, self.history.sprints());
+		self.history.record_sprint(record);
+		if let Err(err) = self.history.save() {
+			eprintln!("Could not save history: {}", err);
+		}
+
+		let dialog = adw::AlertDialog::new(Some(&i18n("Sprint Finished")), Some(&body));
+		dialog.add_response("close", &i18n("_Close"));
+		if let Some(window) = main_application().active_window() {
+			dialog.present(&window);
+		}
+	}
+
+	fn refresh_analysis(&self) {
+		self.analyzer.emit(AnalyzerMsg::Frequencies);
+		self.analyzer.emit(AnalyzerMsg::Rhythm);
+	}
+
+	fn punctuation_locale(&self) -> Locale {
+		self.metadata.language.as_deref()
+			.map(Locale::from_language)
+			.unwrap_or_else(Locale::system)
+	}
+
+	/// Shows the language of the writing in the menu.
+	fn refresh_language(&self) {
+		let language = self.metadata.language.clone().unwrap_or_default();
+		main_application().change_action_state("language", &language.to_variant());
+	}
+
+	/// Replaces the character typed at `offset` by its typographic form, in an
+	/// undo step of its own, so that undoing it gives back what was typed.
+	fn punctuate(&self, offset: i32, typed: char) {
+		let buffer: &gtk::TextBuffer = self.buffer.upcast_ref();
+		let typed_at = buffer.iter_at_offset(offset);
+		let mut end = typed_at.clone();
+		end.forward_char();
+		// Other edits may have come in since, such as a paste.
+		if typed_at.char() != typed || buffer.cursor_position() != end.offset() {
+			return;
+		}
+		let mut line_start = typed_at.clone();
+		line_start.set_line_offset(0);
+		let before = buffer.text(&line_start, &typed_at, true);
+		let lines_before = (0..typed_at.line()).rev().filter_map(|line| {
+			let start = buffer.iter_at_line(line)?;
+			let mut end = start.clone();
+			if !end.ends_line() {
+				end.forward_to_line_end();
+			}
+			Some(buffer.text(&start, &end, true))
+		});
+		let is_prose = self.document.is_prose_at(typed_at.line() as usize, &before);
+		if !is_prose || punctuation::is_in_code_block(&before, lines_before) {
+			return;
+		}
+		let Some(substitution) = punctuation::substitute(self.punctuation_locale(), &before, typed) else {
+			return;
+		};
+		let mut start = typed_at.clone();
+		start.backward_chars(substitution.replaced as i32);
+		self.edit_quietly(|buffer| {
+			buffer.begin_user_action();
+			buffer.delete(&mut start, &mut end);
+			buffer.insert(&mut start, &substitution.text);
+			buffer.end_user_action();
+		});
+	}
+
+	/// Applies smart punctuation to the whole of the text, in one undo step.
+	fn convert_punctuation(&self) {
+		let buffer: &gtk::TextBuffer = self.buffer.upcast_ref();
+		let (mut start, mut end) = buffer.bounds();
+		let text = buffer.text(&start, &end, true);
+		let converted = punctuation::convert(&text, self.punctuation_locale());
+		if converted == text.as_str() {
+			return;
+		}
+		let cursor = buffer.cursor_position();
+		self.edit_quietly(|buffer| {
+			buffer.begin_user_action();
+			buffer.delete(&mut start, &mut end);
+			buffer.insert(&mut start, &converted);
+			buffer.end_user_action();
+		});
+		buffer.place_cursor(&buffer.iter_at_offset(cursor));
+	}
+
+	fn refresh_style_scheme(&mut self) {
+		let style_scheme = theme::scheme_id(&self.text_style_manager, &self.settings.string("theme"), self.is_dark);
+		self.set_style_scheme(style_scheme);
+	}
+
+	fn refresh_preview(&self) {
+		if self.is_previewing {
+			self.analyzer.emit(AnalyzerMsg::Preview);
+		}
+	}
+
+	fn show_preview(&self, html: &str) {
+		if !self.is_previewing {
+			return;
+		}
+		let script = format!(
+			"{}{}",
+			preview::style_script(&self.typography.preview_css()),
+			preview::content_script(html),
+		);
+		self.preview_view.evaluate_javascript(&script, None, None, None::<&gio::Cancellable>, |result| {
+			if let Err(err) = result {
+				eprintln!("Could not render preview: {}", err);
+			}
+		});
+		self.sync_preview();
+	}
+
+	/// Scrolls the preview to the section at the top of the text view.
+	fn sync_preview(&self) {
+		if !self.is_previewing {
+			return;
+		}
+		let origin = gtk::graphene::Point::new(0.0, 0.0);
+		let Some(point) = self.text_view.compute_point(&self.text_scroller, &origin) else {
+			return;
+		};
+		let (iter, _) = self.text_view.line_at_y((-point.y()).max(0.0) as i32);
+		let (anchor, fraction) = preview::position(&self.document, iter.line() as usize);
+		self.preview_view.evaluate_javascript(&preview::scroll_script(&anchor, fraction), None, None, None::<&gio::Cancellable>, |result| {
+			if let Err(err) = result {
+				eprintln!("Could not scroll preview: {}", err);
+			}
+		});
+	}
+
+	fn jump_to_sentence(&mut self, line: usize, offset: usize) {
+		let Some(iter) = self.buffer.iter_at_line_offset(line as i32, offset as i32) else {
+			return;
+		};
+		self.set_editor_mode(EditorMode::Text);
+		self.buffer.place_cursor(&iter);
+		let mark = self.buffer.create_mark(None, &iter, true);
+		self.text_view.scroll_to_mark(&mark, 0.1, false, 0.0, 0.0);
+		self.buffer.delete_mark(&mark);
+		self.text_view.grab_focus();
+	}
+
+	/// Marks every occurrence of `term` in the text, or clears the marks when
+	/// `term` already was.
+	fn highlight_term(&mut self, term: String) {
+		let table = self.buffer.tag_table();
+		let tag = table.lookup("frequency").unwrap_or_else(|| {
+			let tag = gtk::TextTag::builder()
+				.name("frequency")
+				.background_rgba(&gtk::gdk::RGBA::new(0.96, 0.83, 0.18, 0.45))
+				.build();
+			table.add(&tag);
+			tag
+		});
+		let (start, end) = self.buffer.bounds();
+		self.buffer.remove_tag(&tag, &start, &end);
+		if self.highlighted_term.as_ref() == Some(&term) {
+			self.highlighted_term = None;
+			return;
+		}
+
+		let flags = gtk::TextSearchFlags::CASE_INSENSITIVE | gtk::TextSearchFlags::TEXT_ONLY;
+		let mut first = None;
+		let mut iter = self.buffer.start_iter();
+		while let Some((match_start, match_end)) = iter.forward_search(&term, flags, None) {
+			if match_start.starts_word() && match_end.ends_word() {
+				self.buffer.apply_tag(&tag, &match_start, &match_end);
+				first.get_or_insert(match_start);
+			}
+			iter = match_end;
+		}
+		if let Some(first) = first {
+			let mark = self.buffer.create_mark(None, &first, true);
+			self.text_view.scroll_to_mark(&mark, 0.1, false, 0.0, 0.0);
+			self.buffer.delete_mark(&mark);
+		}
+		self.highlighted_term = Some(term);
+	}
+
+	fn export_history(&self) {
+		let mut titles: HashMap<String, String> = Writing::load_all().into_iter()
+			.map(|writing| (writing.id, writing.title))
+			.chain(Project::load_all().into_iter().map(|project| (project.id, project.title)))
+			.collect();
+		// What is open may have been renamed since it was last saved.
+		match &self.project {
+			Some(project) => titles.insert(project.id.clone(), project.title.clone()),
+			None => titles.insert(self.writing_id.clone(), self.title.clone()),
+		};
+		let csv = self.history.to_csv(&titles);
+		let dialog = gtk::FileDialog::builder()
+			.title(i18n("Export History"))
+			.initial_name("history.csv")
+			.modal(true)
+			.build();
+		dialog.save(main_application().active_window().as_ref(), None::<&gio::Cancellable>, move |result| {
+			let Some(path) = result.ok().and_then(|file| file.path()) else {
+				return;
+			};
+			if let Err(err) = std::fs::write(path, csv) {
+				eprintln!("Could not export history: {}", err);
+			}
+		});
+	}
+
+	/// Copies the editor's text back into the open document of the project.
+	fn sync_open_document(&mut self) {
+		let (Some(project), Some(id)) = (&mut self.project, &self.open_document) else {
+			return;
+		};
+		if let Some(item) = project.find_mut(id) {
+			item.text = self.document.text();
+			item.outline = self.outline.saved();
+			self.word_counts.insert(id.clone(), self.document.word_count());
+		}
+	}
+
+	fn open_document(&mut self, id: String) {
+		self.sync_outline();
+		self.sync_open_document();
+		let Some((text, outline)) = self.project.as_ref().and_then(|project| project.find(&id)).map(|item| (item.text.clone(), item.outline.clone())) else {
+			return;
+		};
+		self.open_document = Some(id);
+		set_text_irreversibly(&self.buffer, &text);
+		self.restore_outline(outline);
+		self.refresh_binder();
+	}
+
+	fn open_project(&mut self, project: Project) {
+		if let Err(err) = self.save() {
+			eprintln!("Could not save writing: {}", err);
+		}
+		self.metadata = project.metadata.clone();
+		self.refresh_language();
+		let first = project.flatten().into_iter()
+			.map(|(_, item)| item)
+			.find(|item| matches!(item.kind, DocumentKind::Chapter | DocumentKind::Scene))
+			.or_else(|| project.items.first())
+			.map(|item| item.id.clone());
+		self.open_document = None;
+		self.project = Some(project);
+		self.count_project_words();
+		match first {
+			Some(id) => self.open_document(id),
+			None => self.create_document(DocumentKind::Chapter),
+		}
+		self.set_visible_window_page(WindowPage::Editor);
+		self.set_visible_sidebar_page(SidebarPage::Binder);
+	}
+
+	fn create_document(&mut self, kind: DocumentKind) {
+		let Some(project) = &mut self.project else {
+			return;
+		};
+		let item = BinderItem::new(kind, kind.label(), String::new());
+		let id = item.id.clone();
+		project.insert_after(self.open_document.as_deref(), item);
+		self.open_document(id);
+	}
+
+	fn confirm_delete_document(&mut self, id: String, sender: ComponentSender<Self>) {
+		let Some(item) = self.project.as_ref().and_then(|project| project.find(&id)) else {
+			return;
+		};
+		let dialog = adw::AlertDialog::new(
+			Some(&i18n("Delete Document?")),
+			Some(&if item.children.is_empty() {
+				i18n("The document will be deleted for good")
+			} else {
+				i18n("The document and the documents in it will be deleted for good")
+			}),
+		);
+		dialog.add_response("cancel", &i18n("_Cancel"));
+		dialog.add_response("delete", &i18n("_Delete"));
+		dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+		dialog.set_default_response(Some("cancel"));
+		dialog.set_close_response("cancel");
+		dialog.connect_response(None, move |_, response| {
+			if response == "delete" {
+				sender.input(AppMsg::DeleteDocument(id.clone()));
+			}
+		});
+		if let Some(window) = main_application().active_window() {
+			dialog.present(&window);
+		}
+	}
+
+	fn refresh_binder(&mut self) {
+		let mut guard = self.binder.guard();
+		guard.clear();
+		let Some(project) = &self.project else {
+			return;
+		};
+		for (depth, item) in project.flatten() {
+			let is_open = self.open_document.as_deref() == Some(item.id.as_str());
+			guard.push_back((item.id.clone(), item.title.clone(), item.kind, depth, is_open));
+		}
+	}
+
+	fn refresh_projects(&mut self) {
+		let mut guard = self.projects.guard();
+		guard.clear();
+		for project in Project::load_all() {
+			guard.push_back((project.id.clone(), project.title.clone(), project.flatten().len()));
+		}
+	}
+
+	fn refresh_writings(&mut self) {
+		let mut guard = self.writings.guard();
+		guard.clear();
+		for writing in Writing::load_all() {
+			let excerpt = Document::new(&writing.text, self.counting_rules).excerpt(EXCERPT_LINES);
+			guard.push_back((writing.id, writing.title, excerpt));
+		}
+	}
+
+	fn compile_project(&mut self) {
+		self.sync_open_document();
+		let Some(project) = &self.project else {
+			return;
+		};
+		let manuscript = project.compile();
+		let dialog = gtk::FileDialog::builder()
+			.title(i18n("Compile Manuscript"))
+			.initial_name(format!("{}.md", project.title))
+			.modal(true)
+			.build();
+		dialog.save(main_application().active_window().as_ref(), None::<&gio::Cancellable>, move |result| {
+			let Some(path) = result.ok().and_then(|file| file.path()) else {
+				return;
+			};
+			if let Err(err) = std::fs::write(path, manuscript) {
+				eprintln!("Could not compile manuscript: {}", err);
+			}
+		});
+	}
+
+	fn load_writing(&mut self, writing: Writing) {
+		self.writing_id = writing.id;
+		self.writing_modified = writing.modified;
+		self.metadata = writing.metadata;
+		self.refresh_language();
+		set_text_irreversibly(&self.title_buffer, &writing.title);
+		set_text_irreversibly(&self.buffer, &writing.text);
+		self.restore_outline(writing.outline);
+	}
+
+	/// Moves the section and its subsections out into a new stored writing,
+	/// along with their metadata.
+	fn split_section(&mut self, id: SectionId) {
+		let Some(index) = self.find_live_section(id) else {
+			return;
+		};
+		let Some(range) = self.document.section_tree_range(index) else {
+			return;
+		};
+		let title = self.outline.entries()[index].name.trim_start_matches('#').trim().to_string();
+		let outline: Vec<(SectionId, String)> = self.document.section_ranges().into_iter()
+			.zip(self.outline.saved())
+			.filter(|(section, _)| range.contains(&section.start))
+			.map(|(_, section)| section)
+			.collect();
+
+		let mut writing = Writing::new();
+		writing.title = title;
+		writing.text = self.document.lines_text(range.clone());
+		for (id, _) in outline.iter() {
+			if let Some(section) = self.metadata.sections.get(id) {
+				writing.metadata.sections.insert(*id, section.clone());
+			}
+		}
+		writing.outline = outline;
+		if let Err(err) = writing.save() {
+			eprintln!("Could not save writing: {}", err);
+			return;
+		}
+		// The words of the section were written here, so their share of the
+		// history goes along with them.
+		let share = Document::new(&writing.text, self.counting_rules).word_count() as f64 / self.document.word_count().max(1) as f64;
+		let id = self.history_id();
+		self.history.split(&id, &writing.id, share);
+		for (id, _) in writing.outline.iter() {
+			self.metadata.sections.remove(id);
+		}
+
+		let Some(mut start) = self.buffer.iter_at_line(range.start as i32) else {
+			return;
+		};
+		let mut end = match self.buffer.iter_at_line(range.end as i32) {
+			Some(iter) => iter,
+			None => {
+				// The section runs to the end, so take the line break before it too.
+				start.backward_char();
+				self.buffer.end_iter()
+			},
+		};
+		self.edit_quietly(|buffer| {
+			buffer.begin_user_action();
+			buffer.delete(&mut start, &mut end);
+			buffer.end_user_action();
+		});
+	}
+
+	fn choose_merge_target(&mut self, sender: ComponentSender<Self>) {
+		let writings: Vec<Writing> = Writing::load_all().into_iter()
+			.filter(|writing| writing.id != self.writing_id)
+			.collect();
+		let dialog = adw::AlertDialog::new(
+			Some(&i18n("Merge Into…")),
+			Some(&if writings.is_empty() {
+				i18n("There are no other writings to merge into")
+			} else {
+				i18n("This writing will be appended to the chosen one as a new section")
+			}),
+		);
+		dialog.add_response("cancel", &i18n("_Cancel"));
+		for writing in writings.iter() {
+			let title = if writing.title.is_empty() { i18n("Untitled") } else { writing.title.clone() };
+			dialog.add_response(&writing.id, &title);
+		}
+		dialog.set_close_response("cancel");
+		dialog.connect_response(None, move |_, response| {
+			if response != "cancel" {
+				sender.input(AppMsg::MergeInto(response.to_string()));
+			}
+		});
+		if let Some(window) = main_application().active_window() {
+			dialog.present(&window);
+		}
+	}
+
+	/// Appends the current writing to the writing `id` as a new section, and
+	/// continues editing the result.
+	fn merge_into(&mut self, id: String) {
+		let Some(mut target) = Writing::load_all().into_iter().find(|writing| writing.id == id) else {
+			return;
+		};
+		self.sync_outline();
+		let source = self.current_writing();
+		let title = if source.title.is_empty() { i18n("Untitled") } else { source.title.clone() };
+		target.text = format!("{}\n\n# {}\n{}", target.text.trim_end(), title, source.text);
+		// The heading added in between gets an id of its own once the text is loaded.
+		target.outline.extend(source.outline);
+		target.metadata.merge(source.metadata);
+		self.history.merge(&source.id, &target.id);
+		if let Err(err) = target.save() {
+			eprintln!("Could not save writing: {}", err);
+			return;
+		}
+		if let Err(err) = Writing::delete(&source.id) {
+			eprintln!("Could not delete writing: {}", err);
+		}
+		self.load_writing(target);
+	}
+
+	fn choose_project_template(&mut self, sender: ComponentSender<Self>) {
+		let dialog = adw::AlertDialog::new(
+			Some(&i18n("Develop into Project")),
+			Some(&i18n("Choose how to structure the project. The idea will be kept as its premise.")),
+		);
+		dialog.add_response("cancel", &i18n("_Cancel"));
+		for template in ProjectTemplate::ALL {
+			dialog.add_response(template.id(), &template.label());
+		}
+		dialog.set_close_response("cancel");
+		dialog.connect_response(None, move |_, response| {
+			if let Some(template) = ProjectTemplate::from_id(response) {
+				sender.input(AppMsg::DevelopIntoProject(template));
+			}
+		});
+		if let Some(window) = main_application().active_window() {
+			dialog.present(&window);
+		}
+	}
+
+	/// Converts the current writing into a project, keeping its text as the premise.
+	fn develop_into_project(&mut self, template: ProjectTemplate) {
+		if self.project.is_some() {
+			return;
+		}
+		self.sync_outline();
+		let writing = self.current_writing();
+		let id = writing.id.clone();
+		let title = if writing.title.is_empty() { i18n("Untitled Project") } else { writing.title.clone() };
+		let project = template.build(title, writing);
+		self.history.merge(&id, &project.id);
+		// Saved before it is opened, while the editor still holds the writing.
+		if let Err(err) = project.save() {
+			eprintln!("Could not save project: {}", err);
+			return;
+		}
+		self.open_project(project);
+		// The project replaces the writing it was developed from.
+		if let Err(err) = Writing::delete(&id) {
+			eprintln!("Could not delete writing: {}", err);
+		}
+		self.refresh_projects();
+	}
+
+	fn choose_template(&mut self, sender: ComponentSender<Self>) {
+		relm4::view! {
+			dialog = adw::Dialog {
+				set_title: &i18n("New Writing"),
+				set_content_width: 360,
+
+				#[wrap(Some)]
+				set_child = &adw::ToolbarView {
+					add_top_bar = &adw::HeaderBar {
+						pack_start: import_button = &gtk::Button {
+							set_icon_name: "document-open-symbolic",
+							set_tooltip_text: Some(&i18n("Import Template")),
+						},
+					},
+
+					#[wrap(Some)]
+					set_content = &gtk::ScrolledWindow {
+						set_propagate_natural_height: true,
+						set_hscrollbar_policy: gtk::PolicyType::Never,
+
+						gtk::Box {
+							set_orientation: gtk::Orientation::Vertical,
+							set_spacing: 12,
+							set_margin_top: 12,
+							set_margin_bottom: 12,
+							set_margin_start: 12,
+							set_margin_end: 12,
+
+							gtk::ListBox {
+								add_css_class: "boxed-list",
+								set_selection_mode: gtk::SelectionMode::None,
+
+								append: title_row = &adw::EntryRow {
+									set_title: &i18n("Title"),
+								},
+							},
+
+							append: template_list = &gtk::ListBox {
+								add_css_class: "boxed-list",
+								set_selection_mode: gtk::SelectionMode::None,
+							},
+						},
+					},
+				},
+			}
+		}
+
+		let templates = std::iter::once(None).chain(Template::load_all().into_iter().map(Some));
+		for template in templates {
+			let row = adw::ActionRow::builder()
+				.title(template.as_ref().map(|template| template.name.clone()).unwrap_or_else(|| i18n("Blank")))
+				.activatable(true)
+				.build();
+			if template.as_ref().is_some_and(|template| !template.is_builtin) {
+				row.set_subtitle(&i18n("Custom template"));
+			}
+			if let Some(template) = template.clone() {
+				let export_button = gtk::Button::builder()
+					.icon_name("document-save-symbolic")
+					.tooltip_text(i18n("Export Template"))
+					.valign(gtk::Align::Center)
+					.css_classes(["flat"])
+					.build();
+				let sender = sender.clone();
+				export_button.connect_clicked(move |_| {
+					sender.input(AppMsg::ExportTemplate(template.clone()));
+				});
+				row.add_suffix(&export_button);
+			}
+			let sender = sender.clone();
+			let title_row = title_row.clone();
+			let dialog = dialog.clone();
+			row.connect_activated(move |_| {
+				sender.input(AppMsg::NewWriting(title_row.text().to_string(), template.clone()));
+				dialog.close();
+			});
+			template_list.append(&row);
+		}
+
+		{
+			let dialog = dialog.clone();
+			import_button.connect_clicked(move |_| {
+				dialog.close();
+				sender.input(AppMsg::ImportTemplate);
+			});
+		}
+
+		if let Some(window) = main_application().active_window() {
+			dialog.present(&window);
+		}
+	}
+
+	/// Stores away what is being edited, closing the project if one is open.
+	fn close(&mut self) {
+		if let Err(err) = self.save() {
+			eprintln!("Could not save writing: {}", err);
+		}
+		self.project = None;
+		self.open_document = None;
+		self.refresh_binder();
+	}
+
+	/// Continues editing the stored writing `id`.
+	fn open_writing(&mut self, id: String) {
+		if self.project.is_some() || id != self.writing_id {
+			let Some(writing) = Writing::load_by_id(&id) else {
+				return;
+			};
+			self.close();
+			self.load_writing(writing);
+		}
+		self.set_visible_window_page(WindowPage::Editor);
+	}
+
+	/// Stores away what is being edited and starts a new writing, optionally
+	/// from a template.
+	fn new_writing(&mut self, title: String, template: Option<Template>) {
+		self.close();
+
+		let mut writing = Writing::new();
+		let mut cursor = None;
+		if let Some(template) = template {
+			let placeholder = if title.is_empty() { i18n("Untitled") } else { title.clone() };
+			(writing.text, cursor) = template.expand(&placeholder);
+		}
+		writing.title = title;
+		self.load_writing(writing);
+		if let Some(offset) = cursor {
+			let iter = self.buffer.iter_at_offset(offset as i32);
+			self.buffer.place_cursor(&iter);
+		}
+		self.set_visible_window_page(WindowPage::Editor);
+	}
+
+	/// Statistics of the whole document, of the section under the cursor and
+	/// of the selection, when there is one.
+	fn compute_stats(&self) -> Vec<(StatScope, TextStats)> {
+		let rules = self.counting_rules;
+		let mut scopes = vec![(StatScope::Document, TextStats::compute(&self.document.text(), rules))];
+		let cursor_line = self.buffer.iter_at_mark(&self.buffer.get_insert()).line() as usize;
+		if let Some(range) = self.document.section_ranges().into_iter().find(|range| range.contains(&cursor_line)) {
+			scopes.push((StatScope::Section, TextStats::compute(&self.document.lines_text(range), rules)));
+		}
+		if let Some((start, end)) = self.buffer.selection_bounds() {
+			scopes.push((StatScope::Selection, TextStats::compute(&self.buffer.slice(&start, &end, false), rules)));
+		}
+		scopes
+	}
+
+	/// Whether the open project is a script, which is timed as spoken too.
+	fn is_script(&self) -> bool {
+		self.project.as_ref()
+			.and_then(|project| project.template)
+			.is_some_and(|template| template.is_script())
+	}
+
+	fn is_section_visible(&self, id: SectionId) -> bool {
+		self.section_filter.map_or(true, |filter| filter == self.metadata.section(id).status)
+	}
+
+	/// Replaces the outline with the one saved along with the text just loaded.
+	fn restore_outline(&mut self, saved: Vec<(SectionId, String)>) {
+		self.outline = Outline::restore(saved);
+		let sections: Vec<_> = self.outline.entries().iter()
+			.map(|entry| (entry.clone(), self.metadata.section(entry.id), self.is_section_visible(entry.id)))
+			.collect();
+		let mut cards = self.cards.guard();
+		cards.clear();
+		for (entry, metadata, _) in sections.iter() {
+			cards.push_back((entry.clone(), metadata.clone()));
+		}
+		drop(cards);
+		let mut guard = self.headings.guard();
+		guard.clear();
+		for section in sections {
+			guard.push_back(section);
+		}
+	}
+
+	/// Brings the outline up to date with the live document, which it trails
+	/// by the analysis otherwise.
+	fn sync_outline(&mut self) {
+		let sections = self.document.sections();
+		self.refresh_headings(sections);
+	}
+
+	fn refresh_headings(&mut self, sections: Vec<(String, usize)>) {
+		let changes = self.outline.update(sections);
+		// Sections gone for good take their metadata along.
+		for change in changes.iter() {
+			if let OutlineChange::Remove(_, id) = change {
+				if self.outline.find(*id).is_none() {
+					self.metadata.sections.remove(id);
+				}
+			}
+		}
+		let inserted: Vec<_> = changes.iter()
+			.filter_map(|change| match change {
+				OutlineChange::Insert(_, entry) => Some((self.metadata.section(entry.id), self.is_section_visible(entry.id))),
+				_ => None,
+			})
+			.collect();
+		let mut cards = self.cards.guard();
+		for change in changes.iter() {
+			match change {
+				OutlineChange::Insert(index, entry) => {
+					cards.insert(*index, (entry.clone(), self.metadata.section(entry.id)));
+				},
+				OutlineChange::Remove(index, _) => {
+					cards.remove(*index);
+				},
+				OutlineChange::Update { index, entry } => {
+					if let Some(card) = cards.get_mut(*index) {
+						card.update(entry.clone());
+					}
+				},
+			}
+		}
+		drop(cards);
+		let mut inserted = inserted.into_iter();
+		let mut guard = self.headings.guard();
+		for change in changes {
+			match change {
+				OutlineChange::Insert(index, entry) => {
+					let (metadata, visible) = inserted.next().unwrap_or_default();
+					guard.insert(index, (entry, metadata, visible));
+				},
+				OutlineChange::Remove(index, _) => {
+					guard.remove(index);
+				},
+				OutlineChange::Update { index, entry } => {
+					if let Some(section) = guard.get_mut(index) {
+						section.update(entry);
+					}
+				},
+			}
+		}
+	}
+
+	fn refresh_section(&mut self, id: SectionId) {
+		let Some((index, _)) = self.outline.find(id) else {
+			return;
+		};
+		let metadata = self.metadata.section(id);
+		let visible = self.is_section_visible(id);
+		if let Some(card) = self.cards.guard().get_mut(index) {
+			card.set_metadata(metadata.clone());
+		}
+		if let Some(section) = self.headings.guard().get_mut(index) {
+			section.set_metadata(metadata);
+			section.set_visible(visible);
+		}
+	}
+
+	/// Position of the section among those of the live document.
+	fn find_live_section(&mut self, id: SectionId) -> Option<usize> {
+		self.sync_outline();
+		self.outline.find(id).map(|(index, _)| index)
+	}
+
+	fn jump_to_section(&mut self, id: SectionId) {
+		let Some(index) = self.find_live_section(id) else {
+			return;
+		};
+		let Some(range) = self.document.section_ranges().get(index).cloned() else {
+			return;
+		};
+		let Some(iter) = self.buffer.iter_at_line(range.start as i32) else {
+			return;
+		};
+		self.set_editor_mode(EditorMode::Text);
+		self.buffer.place_cursor(&iter);
+		let mark = self.buffer.create_mark(None, &iter, true);
+		self.text_view.scroll_to_mark(&mark, 0.0, true, 0.0, 0.0);
+		self.buffer.delete_mark(&mark);
+		self.text_view.grab_focus();
+	}
+
+	/// Moves the section `from` so that it ends up at the position of the
+	/// section `to`, as a single undoable edit.
+	fn move_section(&mut self, from: SectionId, to: SectionId) {
+		let (Some(from), Some(to)) = (self.find_live_section(from), self.find_live_section(to)) else {
+			return;
+		};
+		let ranges = self.document.section_ranges();
+		if from >= ranges.len() || to >= ranges.len() || from == to {
+			return;
+		}
+		// The blank lines after each section stay where they are, keeping the
+		// sections apart and the end of the document as it was.
+		let (mut sections, gaps): (Vec<Vec<&str>>, Vec<Vec<&str>>) = ranges.iter()
+			.map(|range| {
+				let lines: Vec<&str> = range.clone().map(|index| self.document.line(index)).collect();
+				let content = lines.iter().rposition(|line| !line.trim().is_empty()).map_or(0, |index| index + 1);
+				(lines[..content].to_vec(), lines[content..].to_vec())
+			})
+			.unzip();
+		let section = sections.remove(from);
+		sections.insert(to, section);
+		let text = sections.into_iter()
+			.zip(gaps)
+			.flat_map(|(section, gap)| section.into_iter().chain(gap))
+			.collect::<Vec<_>>()
+			.join("\n");
+
+		let Some(mut start) = self.buffer.iter_at_line(ranges[0].start as i32) else {
+			return;
+		};
+		let mut end = self.buffer.end_iter();
+		self.edit_quietly(|buffer| {
+			buffer.begin_user_action();
+			buffer.delete(&mut start, &mut end);
+			buffer.insert(&mut start, &text);
+			buffer.end_user_action();
+		});
+	}
+
+	fn refresh_section_visibility(&mut self) {
+		let visibility: Vec<bool> = self.outline.entries().iter()
+			.map(|entry| self.is_section_visible(entry.id))
+			.collect();
+		let mut guard = self.headings.guard();
+		for (index, visible) in visibility.into_iter().enumerate() {
+			// Only touch the rows whose visibility actually flips.
+			if guard.get(index).is_some_and(|section| section.visible() != visible) {
+				if let Some(section) = guard.get_mut(index) {
+					section.set_visible(visible);
+				}
+			}
+		}
 	}
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for App {
-	type Init = (SidebarPage, sourceview5::StyleSchemeManager, WindowPage, glib::Bytes);
+	type Init = (SidebarPage, sourceview5::StyleSchemeManager, WindowPage, glib::Bytes, glib::Bytes, glib::Bytes, Option<sourceview5::Language>);
 	type Input = AppMsg;
 	type Output = ();
 
@@ -139,6 +1378,39 @@ impl SimpleComponent for App {
 				&i18n("_Formatting") => FormattingAction,
 			},
 
+			section! {
+				&i18n("_Hide Markup") => HideMarkupAction,
+				&i18n("_Focus Mode") => FocusModeAction,
+				&i18n("_Typewriter Scrolling") => TypewriterScrollingAction,
+			},
+
+			section! {
+				&i18n("Focus on _Sentence") => FocusScopeAction("sentence".to_string()),
+				&i18n("Focus on _Paragraph") => FocusScopeAction("paragraph".to_string()),
+			},
+
+			section! {
+				&i18n("S_mart Punctuation") => SmartPunctuationAction,
+				&i18n("_Convert Punctuation") => ConvertPunctuationAction,
+			},
+
+			section! {
+				&i18n("Punctuation of the _User Language") => LanguageAction(String::new()),
+				&i18n("_English Punctuation") => LanguageAction(Locale::English.id().to_string()),
+				&i18n("F_rench Punctuation") => LanguageAction(Locale::French.id().to_string()),
+				&i18n("_German Punctuation") => LanguageAction(Locale::German.id().to_string()),
+			},
+
+			section! {
+				&i18n("Zoom _In") => ZoomInAction,
+				&i18n("Zoom _Out") => ZoomOutAction,
+				&i18n("_Reset Zoom") => ZoomResetAction,
+			},
+
+			section! {
+				&i18n("_Preferences") => PreferencesAction,
+			},
+
 			section! {
 				&i18n("_Delete") => DeleteAction,
 			}
@@ -157,13 +1429,10 @@ impl SimpleComponent for App {
 			},
 		},
 
-		editor_title_text_buffer = sourceview5::Buffer {
-			#[track = "model.changed(App::is_dark())"]
-			set_style_scheme: {
-				text_style_manager.scheme({
-					if model.is_dark { "austeur-default-dark" } else { "austeur-default" }
-				}.as_ref()).as_ref()
-			},
+		#[local_ref]
+		editor_title_text_buffer -> sourceview5::Buffer {
+			#[track = "model.changed(App::style_scheme())"]
+			set_style_scheme: text_style_manager.scheme(&model.style_scheme).as_ref(),
 
 			connect_changed[sender] => move |buffer| {
 				let (start, end) = buffer.bounds();
@@ -172,18 +1441,66 @@ impl SimpleComponent for App {
 			},
 		},
 
-		text_view_buffer = sourceview5::Buffer {
-			#[track = "model.changed(App::is_dark())"]
-			set_style_scheme: {
-				text_style_manager.scheme({
-					if model.is_dark { "austeur-default-dark" } else { "austeur-default" }
-				}.as_ref()).as_ref()
+		#[local_ref]
+		text_view_buffer -> sourceview5::Buffer {
+			set_language: markdown.as_ref(),
+
+			#[track = "model.changed(App::style_scheme())"]
+			set_style_scheme: text_style_manager.scheme(&model.style_scheme).as_ref(),
+
+			connect_style_scheme_notify => move |buffer| {
+				focus::refresh_color(buffer);
 			},
 
-			connect_changed[sender] => move |buffer| {
-				let (start, end) = buffer.bounds();
-				let text = buffer.slice(&start, &end, true);
-				sender.input(AppMsg::ChangeText(text.into()));
+			connect_cursor_position_notify[sender] => move |buffer| {
+				sender.input(AppMsg::MoveCursor(buffer.iter_at_offset(buffer.cursor_position()).line()));
+			},
+
+			// Only the lines touched by an edit are sent over, see `AppMsg::ChangeLines`.
+			connect_insert_text[sender, pending_edit = pending_edit.clone(), user_action_depth = user_action_depth.clone()] => move |buffer, iter, text| {
+				pending_edit.set(Some((iter.line(), 1, buffer.line_count())));
+				let mut chars = text.chars();
+				if let (Some(typed), None, true) = (chars.next(), chars.next(), user_action_depth.get() > 0) {
+					sender.input(AppMsg::Punctuate { offset: iter.offset(), typed });
+				}
+			},
+
+			connect_delete_range[pending_edit = pending_edit.clone()] => move |buffer, start, end| {
+				pending_edit.set(Some((start.line(), end.line() - start.line() + 1, buffer.line_count())));
+			},
+
+			connect_begin_user_action[user_action_depth = user_action_depth.clone()] => move |_| {
+				user_action_depth.set(user_action_depth.get() + 1);
+			},
+
+			connect_end_user_action[user_action_depth = user_action_depth.clone()] => move |_| {
+				user_action_depth.set(user_action_depth.get().saturating_sub(1));
+			},
+
+			connect_changed[sender, pending_edit, user_action_depth, suppress_history = suppress_history.clone()] => move |buffer| {
+				let Some((start, removed, line_count)) = pending_edit.take() else {
+					return;
+				};
+				// Told by the lines the buffer counts, as a carriage return may
+				// join a line feed inserted after it into a single break.
+				let added = removed + buffer.line_count() - line_count;
+				let from = buffer.iter_at_line(start).unwrap_or_else(|| buffer.start_iter());
+				let to = buffer.iter_at_line(start + added)
+					.map(|mut iter| {
+						iter.backward_char();
+						iter
+					})
+					.unwrap_or_else(|| buffer.end_iter());
+				let lines = split_lines(&buffer.slice(&from, &to, true))
+					.into_iter()
+					.map(String::from)
+					.collect();
+				sender.input(AppMsg::ChangeLines {
+					start: start as usize,
+					removed: removed as usize,
+					lines,
+					typed: user_action_depth.get() > 0 && !suppress_history.get(),
+				});
 			},
 		},
 
@@ -197,6 +1514,9 @@ impl SimpleComponent for App {
 				glib::Propagation::Stop
 			},
 
+			#[track = "model.changed(App::is_sprinting()) || model.changed(App::is_focused())"]
+			set_fullscreened: model.is_sprinting || model.is_focused,
+
 			#[transition = "Crossfade"]
 			match model.visible_window_page {
 				WindowPage::Home => {
@@ -216,7 +1536,7 @@ impl SimpleComponent for App {
 								set_halign: gtk::Align::End,
 
 								connect_clicked[sender] => move |_| {
-									sender.input(AppMsg::SwitchWindowPage(WindowPage::Editor));
+									sender.input(AppMsg::ChooseTemplate);
 								},
 
 								gtk::Box {
@@ -256,52 +1576,39 @@ impl SimpleComponent for App {
 									set_orientation: gtk::Orientation::Vertical,
 
 									adw::PreferencesGroup {
-										set_title: "Ideas",
-										set_description: Some(&i18n("Quickly write an idea with the + button above and it'll appear here")),
+										set_title: &i18n("Activity"),
 										#[watch]
-										set_visible: model.title.len() > 0 || model.text.len() > 0,
-
-										gtk::ListBox {
-											add_css_class: "boxed-list",
-											set_selection_mode: gtk::SelectionMode::None,
+										set_description: Some(&ni18n_f("{count} day streak", "{count} day streak", model.streak, &[("count", &model.streak.to_string())])),
 
-											adw::ActionRow {
-												#[watch]
-												set_title: {
-													if model.title.len() > 0 {
-														model.title.as_str()
-													} else {
-														""
-													}
-												},
-
-												#[watch]
-												set_subtitle: {
-													if model.text.len() > 0 {
-														model.text.as_str()
-													} else {
-														""
-													}
-												},
+										gtk::DrawingArea {
+											set_content_height: 7 * 12,
+											set_tooltip_text: Some(&i18n("Words written per day")),
+											#[track = "model.changed(App::activity())"]
+											set_draw_func: draw_heatmap(model.activity.clone()),
+										},
+									},
 
-												set_subtitle_lines: 2,
-												set_activatable_widget: Some(&activatable_button),
+									adw::PreferencesGroup {
+										set_title: &i18n("Projects"),
+										#[watch]
+										set_visible: !model.projects.is_empty(),
 
-												add_suffix: activatable_button = &gtk::Button {
-													set_valign: gtk::Align::Center,
-													add_css_class: "flat",
+										#[local_ref]
+										projects_container -> gtk::ListBox {
+											add_css_class: "boxed-list",
+											set_selection_mode: gtk::SelectionMode::None,
+										},
+									},
 
-													connect_clicked[sender] => move |_| {
-														sender.input(AppMsg::SwitchWindowPage(WindowPage::Editor));
-													},
+									adw::PreferencesGroup {
+										set_title: &i18n("Ideas"),
+										set_description: Some(&i18n("Quickly write an idea with the + button above and it'll appear here")),
 
-													gtk::Image {
-														set_icon_name: Some("go-next-symbolic"),
-														add_css_class: "dim-label",
-													},
-												},
-											},
-										}
+										#[local_ref]
+										writings_container -> gtk::ListBox {
+											add_css_class: "boxed-list",
+											set_selection_mode: gtk::SelectionMode::None,
+										},
 									},
 								},
 							},
@@ -311,6 +1618,11 @@ impl SimpleComponent for App {
 
 				WindowPage::Editor => {
 					adw::NavigationSplitView {
+						// Nothing but the text while a sprint runs, or in focus mode.
+						#[track = "model.changed(App::is_sprinting()) || model.changed(App::is_focused())"]
+						set_collapsed: model.is_sprinting || model.is_focused,
+						set_show_content: true,
+
 						#[wrap(Some)]
 						set_sidebar = &adw::NavigationPage {
 							#[wrap(Some)]
@@ -320,6 +1632,19 @@ impl SimpleComponent for App {
 									set_title_widget = &gtk::Box {
 										add_css_class: "linked",
 
+										gtk::ToggleButton {
+											set_icon_name: "folder-symbolic",
+											set_tooltip_text: Some(&i18n("Binder")),
+											add_css_class: "wide",
+											add_css_class: "flat",
+											#[watch]
+											set_active: model.visible_sidebar_page == SidebarPage::Binder,
+
+											connect_clicked[sender] => move |_| {
+												sender.input(AppMsg::SwitchSidebarPage(SidebarPage::Binder));
+											},
+										},
+
 										gtk::ToggleButton {
 											set_icon_name: "text-justify-left-symbolic",
 											set_tooltip_text: Some(&i18n("Sections")),
@@ -333,6 +1658,19 @@ impl SimpleComponent for App {
 											},
 										},
 
+										gtk::ToggleButton {
+											set_icon_name: "uppercase-symbolic",
+											set_tooltip_text: Some(&i18n("Formatting")),
+											add_css_class: "wide",
+											add_css_class: "flat",
+											#[watch]
+											set_active: model.visible_sidebar_page == SidebarPage::Formatting,
+
+											connect_clicked[sender] => move |_| {
+												sender.input(AppMsg::SwitchSidebarPage(SidebarPage::Formatting));
+											},
+										},
+
 										gtk::ToggleButton {
 											set_icon_name: "text-squiggly-symbolic",
 											set_tooltip_text: Some(&i18n("Spell Check")),
@@ -371,6 +1709,19 @@ impl SimpleComponent for App {
 												sender.input(AppMsg::SwitchSidebarPage(SidebarPage::History));
 											},
 										},
+
+										gtk::ToggleButton {
+											set_icon_name: "view-list-ordered-symbolic",
+											set_tooltip_text: Some(&i18n("Word Frequency")),
+											add_css_class: "wide",
+											add_css_class: "flat",
+											#[watch]
+											set_active: model.visible_sidebar_page == SidebarPage::Analysis,
+
+											connect_clicked[sender] => move |_| {
+												sender.input(AppMsg::SwitchSidebarPage(SidebarPage::Analysis));
+											},
+										},
 									},
 
 									pack_start = &gtk::Button {
@@ -394,10 +1745,113 @@ impl SimpleComponent for App {
 									},
 								},
 
-								#[wrap(Some)]
-								set_content = match model.visible_sidebar_page {
-									SidebarPage::Sections => {
-										gtk::Box {
+								#[wrap(Some)]
+								set_content = match model.visible_sidebar_page {
+									SidebarPage::Binder => {
+										gtk::Box {
+											set_orientation: gtk::Orientation::Vertical,
+
+											if model.project.is_some() {
+												gtk::Box {
+													set_orientation: gtk::Orientation::Vertical,
+													set_spacing: 6,
+
+													gtk::Box {
+														set_spacing: 6,
+														set_margin_top: 6,
+														set_margin_start: 6,
+														set_margin_end: 6,
+
+														gtk::MenuButton {
+															set_icon_name: "plus-symbolic",
+															set_tooltip_text: Some(&i18n("New Document")),
+															set_hexpand: true,
+
+															#[wrap(Some)]
+															set_popover = &gtk::Popover {
+																#[local_ref]
+																new_document_box -> gtk::Box {
+																	set_orientation: gtk::Orientation::Vertical,
+																},
+															},
+														},
+
+														gtk::Button {
+															set_icon_name: "paper-symbolic",
+															set_tooltip_text: Some(&i18n("Compile Manuscript")),
+
+															connect_clicked[sender] => move |_| {
+																sender.input(AppMsg::CompileProject);
+															},
+														},
+													},
+
+													#[local_ref]
+													binder_container -> gtk::Box {
+														add_css_class: "navigation-sidebar",
+														set_orientation: gtk::Orientation::Vertical,
+														set_hexpand: true,
+														set_spacing: 6,
+													},
+												}
+											} else {
+												adw::StatusPage {
+													set_hexpand: true,
+													set_title: &i18n("No Project"),
+													set_description: Some(&i18n("Projects gather chapters, scenes, notes and research in one place")),
+													add_css_class: "compact",
+
+													#[wrap(Some)]
+													set_child = &gtk::Button {
+														set_label: &i18n("New Project"),
+														set_halign: gtk::Align::Center,
+														add_css_class: "pill",
+														add_css_class: "suggested-action",
+
+														connect_clicked[sender] => move |_| {
+															sender.input(AppMsg::NewProject);
+														},
+													},
+												}
+											}
+										}
+									},
+
+									SidebarPage::Sections => {
+										gtk::Box {
+											set_orientation: gtk::Orientation::Vertical,
+
+											gtk::Box {
+												set_spacing: 6,
+												set_margin_top: 6,
+												set_margin_start: 6,
+												set_margin_end: 6,
+
+												gtk::DropDown::from_strings(&section_filter_labels.iter().map(String::as_str).collect::<Vec<_>>()) {
+													set_hexpand: true,
+													set_tooltip_text: Some(&i18n("Filter by Status")),
+
+													connect_selected_notify[sender] => move |dropdown| {
+														let filter = match dropdown.selected() {
+															0 => None,
+															i => SectionStatus::ALL.get(i as usize - 1).copied(),
+														};
+														sender.input(AppMsg::FilterSections(filter));
+													},
+												},
+
+												gtk::Button {
+													set_icon_name: "merge-symbolic",
+													set_tooltip_text: Some(&i18n("Merge Into…")),
+													#[watch]
+													set_sensitive: model.project.is_none(),
+
+													connect_clicked[sender] => move |_| {
+														sender.input(AppMsg::ChooseMergeTarget);
+													},
+												},
+											},
+
 											if model.headings.len() > 0 {
 												gtk::Box {
 													#[local_ref]
@@ -420,8 +1874,9 @@ impl SimpleComponent for App {
 									},
 
 									SidebarPage::Formatting => {
-										gtk::Label {
-											set_label: "formatting",
+										gtk::Box {
+											#[local_ref]
+											formatting_container -> gtk::ScrolledWindow {},
 										}
 									},
 
@@ -438,9 +1893,50 @@ impl SimpleComponent for App {
 									},
 
 									SidebarPage::History => {
-										webkit6::WebView {
+										gtk::Box {
+											set_orientation: gtk::Orientation::Vertical,
+
+											#[local_ref]
+											history_view -> webkit6::WebView {
+												set_vexpand: true,
+												set_settings: history_webview_settings = &webkit6::Settings {
+										    		set_enable_write_console_messages_to_stdout: true,
+										            set_allow_top_navigation_to_data_urls: false,
+										            set_allow_universal_access_from_file_urls: false,
+										            set_enable_back_forward_navigation_gestures: false,
+										            // TODO(blq): Disable this in production builds.
+										            set_enable_developer_extras: true,
+										    	},
+												load_bytes: (&history_html, None, None, None),
+												set_background_color: &gtk::gdk::RGBA::new(0.0,0.0,0.0,0.0),
+
+												connect_load_changed[sender] => move |_, event| {
+													if event == webkit6::LoadEvent::Finished {
+														sender.input(AppMsg::RefreshHistory);
+													}
+												},
+									    	},
+
+											gtk::Button {
+												set_margin_top: 6,
+												set_margin_bottom: 6,
+												set_margin_start: 6,
+												set_margin_end: 6,
+												add_css_class: "flat",
+												set_label: &i18n("Export as CSV"),
+
+												connect_clicked[sender] => move |_| {
+													sender.input(AppMsg::ExportHistory);
+												},
+											},
+										}
+									}
+
+									SidebarPage::Analysis => {
+										#[local_ref]
+										analysis_view -> webkit6::WebView {
 											set_vexpand: true,
-											set_settings: history_webview_settings = &webkit6::Settings {
+											set_settings: analysis_webview_settings = &webkit6::Settings {
 									    		set_enable_write_console_messages_to_stdout: true,
 									            set_allow_top_navigation_to_data_urls: false,
 									            set_allow_universal_access_from_file_urls: false,
@@ -448,8 +1944,14 @@ impl SimpleComponent for App {
 									            // TODO(blq): Disable this in production builds.
 									            set_enable_developer_extras: true,
 									    	},
-											load_bytes: (&history_html, None, None, None),
+											load_bytes: (&analysis_html, None, None, None),
 											set_background_color: &gtk::gdk::RGBA::new(0.0,0.0,0.0,0.0),
+
+											connect_load_changed[sender] => move |_, event| {
+												if event == webkit6::LoadEvent::Finished {
+													sender.input(AppMsg::RefreshAnalysis);
+												}
+											},
 								    	}
 									}
 								},
@@ -459,8 +1961,50 @@ impl SimpleComponent for App {
 						set_content = &adw::NavigationPage {
 							#[wrap(Some)]
 							set_child: a = &adw::ToolbarView {
-								add_top_bar = if model.is_page_empty {
+								#[track = "model.changed(App::is_focused())"]
+								set_reveal_top_bars: !model.is_focused,
+
+								add_top_bar = if model.is_sprinting {
 									adw::HeaderBar {
+										add_css_class: "flat",
+
+										#[wrap(Some)]
+										set_title_widget = &gtk::Label {
+											add_css_class: "title-2",
+											add_css_class: "numeric",
+											#[watch]
+											set_label: &model.sprint.as_ref().map(Sprint::countdown).unwrap_or_default(),
+										},
+
+										pack_start = &gtk::Label {
+											add_css_class: "dim-label",
+											add_css_class: "numeric",
+											set_margin_start: 12,
+											#[watch]
+											set_label: &model.sprint.as_ref().map(Sprint::progress_label).unwrap_or_default(),
+										},
+
+										pack_end = &gtk::Button {
+											set_label: &i18n("Stop"),
+											add_css_class: "flat",
+
+											connect_clicked[sender] => move |_| {
+												sender.input(AppMsg::StopSprint);
+											},
+										},
+									}
+								} else if model.is_page_empty {
+									adw::HeaderBar {
+										pack_start = &gtk::Button {
+											set_icon_name: "timer-symbolic",
+											set_tooltip_text: Some(&i18n("Start Sprint")),
+											add_css_class: "flat",
+
+											connect_clicked[sender] => move |_| {
+												sender.input(AppMsg::ChooseSprint);
+											},
+										},
+
 										pack_end = &gtk::Button {
 											set_tooltip_text: Some(&i18n("Generate Prompt")),
 											#[iterate]
@@ -491,6 +2035,16 @@ impl SimpleComponent for App {
 											set_margin_start: 12,
 										},
 
+										pack_start = &gtk::Button {
+											set_icon_name: "timer-symbolic",
+											set_tooltip_text: Some(&i18n("Start Sprint")),
+											add_css_class: "flat",
+
+											connect_clicked[sender] => move |_| {
+												sender.input(AppMsg::ChooseSprint);
+											},
+										},
+
 										pack_end = &gtk::Button {
 											set_tooltip_text: Some(&i18n("Generate Prompt")),
 											#[iterate]
@@ -502,43 +2056,185 @@ impl SimpleComponent for App {
 											},
 										},
 
+										pack_end = &gtk::ToggleButton {
+											set_icon_name: "view-dual-symbolic",
+											set_tooltip_text: Some(&i18n("Preview")),
+											add_css_class: "flat",
+											#[track = "model.changed(App::is_previewing())"]
+											set_active: model.is_previewing,
+
+											connect_clicked[sender] => move |button| {
+												sender.input(AppMsg::TogglePreview(button.is_active()));
+											},
+										},
+
+										pack_end = &gtk::ToggleButton {
+											set_icon_name: "view-grid-symbolic",
+											set_tooltip_text: Some(&i18n("Corkboard")),
+											add_css_class: "flat",
+											#[watch]
+											set_active: model.editor_mode == EditorMode::Corkboard,
+
+											connect_clicked[sender] => move |button| {
+												let mode = if button.is_active() { EditorMode::Corkboard } else { EditorMode::Text };
+												sender.input(AppMsg::SwitchEditorMode(mode));
+											},
+										},
+
 										pack_end: stat_button = &gtk::ToggleButton {
-											set_tooltip_text: Some(&i18n("Show Statistics")),
-											#[track = "model.changed(App::word_count())"]
-											set_label: &format!("{}", model.word_count),
+											#[track = "model.changed(App::goal_progress())"]
+											set_tooltip_text: Some(&model.goal_progress.summary().unwrap_or_else(|| i18n("Show Statistics"))),
 											#[iterate]
 											add_css_class: vec!["font-medium", "thin", "outlined", "primary"],
 											connect_clicked[sender] => move |_| {
 												sender.input(AppMsg::ToggleStatDialog);
 											},
+
+											gtk::Box {
+												set_spacing: 6,
+
+												gtk::Label {
+													#[track = "model.changed(App::word_count())"]
+													set_label: &format!("{}", model.word_count),
+												},
+
+												gtk::DrawingArea {
+													set_content_width: 16,
+													set_content_height: 16,
+													set_valign: gtk::Align::Center,
+													#[track = "model.changed(App::goal_progress())"]
+													set_visible: model.goal_progress.fraction().is_some(),
+													#[track = "model.changed(App::goal_progress())"]
+													set_draw_func: draw_progress_ring(model.goal_progress.fraction().unwrap_or_default()),
+												},
+											},
 										},
 
 									}
 								},
 
 								#[wrap(Some)]
-								set_content = &gtk::ScrolledWindow {
-									set_vscrollbar_policy: gtk::PolicyType::Automatic,
-									set_hscrollbar_policy: gtk::PolicyType::Never,
+								set_content = match model.editor_mode {
+									EditorMode::Text => {
+										gtk::Box {
+											#[local_ref]
+											text_scroller -> gtk::ScrolledWindow {
+												set_hexpand: true,
+												set_vscrollbar_policy: gtk::PolicyType::Automatic,
+												set_hscrollbar_policy: gtk::PolicyType::Never,
 
-									adw::Clamp {
-										set_maximum_size: 800,
+												adw::Clamp {
+													#[track = "model.changed(App::typography())"]
+													set_maximum_size: model.typography.text_width,
 
-										gtk::Box {
-											set_orientation: gtk::Orientation::Vertical,
-											set_margin_top: 6,
-											set_margin_bottom: 24,
-											set_spacing: 6,
+													gtk::Box {
+														set_orientation: gtk::Orientation::Vertical,
+														set_margin_top: 6,
+														set_margin_bottom: 24,
+														set_spacing: 6,
+
+														#[local_ref]
+														text_view -> sourceview5::View {
+															add_css_class: "editor",
+															#[track = "model.changed(App::typography())"]
+															set_pixels_below_lines: model.typography.paragraph_spacing,
+															#[track = "model.changed(App::typography())"]
+															set_indent: model.typography.first_line_indent,
+															set_hexpand: true,
+															set_vexpand: true,
+															set_wrap_mode: gtk::WrapMode::WordChar,
+															set_accepts_tab: false,
+															set_left_margin: 16,
+															set_right_margin: 8,
+
+															connect_preedit_changed[is_composing] => move |_, preedit| {
+																is_composing.set(!preedit.is_empty());
+															},
+
+															// Ahead of the text view, which would break the line
+															// plainly or move the focus, but not of an input method
+															// composing text, which Enter commits.
+															add_controller = gtk::EventControllerKey {
+																set_propagation_phase: gtk::PropagationPhase::Capture,
+																connect_key_pressed[buffer = text_view_buffer.clone(), suppress_history = suppress_history.clone(), is_composing = is_composing.clone()] => move |_, key, _, modifiers| {
+																	if is_composing.get() {
+																		return glib::Propagation::Proceed;
+																	}
+																	suppress_history.set(true);
+																	let propagation = continuation::handle_key(buffer.upcast_ref(), key, modifiers);
+																	suppress_history.set(false);
+																	propagation
+																},
+															},
+														},
+													},
+												},
+											},
+
+											gtk::Separator {
+												#[track = "model.changed(App::is_previewing())"]
+												set_visible: model.is_previewing,
+											},
 
-											sourceview5::View::with_buffer(&text_view_buffer) {
+											#[local_ref]
+											preview_view -> webkit6::WebView {
 												set_hexpand: true,
-												set_vexpand: true,
-												set_wrap_mode: gtk::WrapMode::WordChar,
-												set_accepts_tab: false,
-												set_left_margin: 16,
-												set_right_margin: 8,
+												#[track = "model.changed(App::is_previewing())"]
+												set_visible: model.is_previewing,
+												set_settings: preview_webview_settings = &webkit6::Settings {
+										    		set_enable_write_console_messages_to_stdout: true,
+										            set_allow_top_navigation_to_data_urls: false,
+										            set_allow_universal_access_from_file_urls: false,
+										            set_enable_back_forward_navigation_gestures: false,
+										            // Scripts in the writing are never run, only ours.
+										            set_enable_javascript_markup: false,
+										            // TODO(blq): Disable this in production builds.
+										            set_enable_developer_extras: true,
+										    	},
+												load_bytes: (&preview_html, None, None, None),
+												set_background_color: &gtk::gdk::RGBA::new(0.0,0.0,0.0,0.0),
+
+												connect_load_changed[sender, preview_loaded = preview_loaded.clone()] => move |_, event| {
+													if event == webkit6::LoadEvent::Finished {
+														preview_loaded.set(true);
+														sender.input(AppMsg::RefreshPreview);
+													}
+												},
+
+												// Links in the writing are not followed.
+												connect_decide_policy[preview_loaded = preview_loaded.clone()] => move |_, decision, _| {
+													if preview_loaded.get() {
+														decision.ignore();
+														true
+													} else {
+														false
+													}
+												},
 											},
-										},
+										}
+									},
+
+									EditorMode::Corkboard => {
+										gtk::ScrolledWindow {
+											set_vscrollbar_policy: gtk::PolicyType::Automatic,
+											set_hscrollbar_policy: gtk::PolicyType::Never,
+
+											#[local_ref]
+											cards_container -> gtk::FlowBox {
+												add_css_class: "corkboard",
+												set_valign: gtk::Align::Start,
+												set_selection_mode: gtk::SelectionMode::None,
+												set_homogeneous: true,
+												set_min_children_per_line: 2,
+												set_max_children_per_line: 6,
+												set_row_spacing: 12,
+												set_column_spacing: 12,
+												set_margin_top: 12,
+												set_margin_bottom: 12,
+												set_margin_start: 12,
+												set_margin_end: 12,
+											}
+										}
 									},
 								},
 							},
@@ -553,10 +2249,17 @@ impl SimpleComponent for App {
 		if model.changed(App::is_stat_dialog_visible()) {
 			if model.is_stat_dialog_visible {
 				let main_window = main_window.clone();
+				let stat_stack = stats_view_stack(&model.stats, model.is_script());
+				let goals_sender = sender.clone();
+				let goals = goals_page(&model.goal_progress, move |goal| {
+					goals_sender.input(AppMsg::ChangeGoal(goal));
+				});
+				stat_stack.add_titled_with_icon(&goals, Some("goals"), &i18n("Goals"), "flag-symbolic");
 				relm4::view! {
 					stat_dialog = adw::Dialog {
-						set_content_width: 300,
-						set_content_height: 300,
+						set_title: &i18n("Statistics"),
+						set_content_width: 360,
+						set_content_height: 560,
 						set_presentation_mode: adw::DialogPresentationMode::Floating,
 						connect_closed[sender] => move |_| {
 							sender.input(AppMsg::ToggleStatDialog);
@@ -566,19 +2269,13 @@ impl SimpleComponent for App {
 			    		set_child = &adw::ToolbarView {
 			    			add_top_bar = &adw::HeaderBar {
 			    				#[wrap(Some)]
-			    				set_title_widget = &adw::WindowTitle {
-			    					set_title: &i18n("Statistics"),
+			    				set_title_widget = &adw::ViewSwitcher {
+			    					set_policy: adw::ViewSwitcherPolicy::Wide,
+			    					set_stack: Some(&stat_stack),
 			    				},
 			    			},
 
-			    			#[wrap(Some)]
-			    			set_content = &gtk::Box {
-			    				set_width_request: 300,
-
-			    				adw::ActionRow {
-			    					set_title: "Hi",
-			    				},
-			    			},
+			    			set_content: Some(&stat_stack),
 			    		}
 			    	},
 				}
@@ -592,42 +2289,224 @@ impl SimpleComponent for App {
 		root: Self::Root,
 		sender: ComponentSender<Self>,
 	) -> ComponentParts<Self> {
-		let (visible_sidebar_page, text_style_manager, visible_window_page, history_html) = init;
+		let (visible_sidebar_page, text_style_manager, visible_window_page, history_html, analysis_html, preview_html, markdown) = init;
 
 		let mut ideas = Vec::<String>::new();
 		ideas.push("51a".to_string()); // placeholder
 
-		let a = App::load_writings();
+		// The most recently edited writing is taken up again.
+		let writing = App::load_writings().into_iter().next().unwrap_or_else(Writing::new);
+
+		let settings = gio::Settings::new(APP_ID);
+		let counting_rules = load_counting_rules(&settings);
+		let suppress_history: Rc<Cell<bool>> = Rc::new(Cell::new(false));
+		let typography = Typography::load(&settings);
+		let css_provider = gtk::CssProvider::new();
+		css_provider.load_from_string(&format!("{}\n{}", theme::accent_css(&settings), typography.css()));
+		let is_dark = adw::StyleManager::default().is_dark();
+		let style_scheme = theme::scheme_id(&text_style_manager, &settings.string("theme"), is_dark);
+		if let Some(display) = gtk::gdk::Display::default() {
+			// Above the style sheet of the application.
+			gtk::style_context_add_provider_for_display(&display, &css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1);
+		}
+		{
+			let sender = sender.clone();
+			settings.connect_changed(None, move |_, _| {
+				sender.input(AppMsg::ChangeSettings);
+			});
+		}
+
+		let editor_title_text_buffer = &sourceview5::Buffer::new(None);
+		let text_view_buffer = &sourceview5::Buffer::new(None);
+		let text_view = &sourceview5::View::with_buffer(text_view_buffer);
+		let history_view = &webkit6::WebView::new();
+		let preview_view = &webkit6::WebView::new();
+		// Once the preview page is in, it is only ever changed by script.
+		let preview_loaded = Rc::new(Cell::new(false));
+		let text_scroller = &gtk::ScrolledWindow::new();
+		// The Analysis page posts what was clicked as JSON: a term to highlight,
+		// or the position of a sentence to go to.
+		let analysis_content_manager = webkit6::UserContentManager::new();
+		analysis_content_manager.register_script_message_handler("austeur", None);
+		{
+			let sender = sender.clone();
+			analysis_content_manager.connect_script_message_received(Some("austeur"), move |_, value| {
+				let Ok(message) = json::parse(value.to_str().as_str()) else {
+					return;
+				};
+				if let Some(term) = message["term"].as_str() {
+					sender.input(AppMsg::HighlightTerm(term.to_string()));
+				} else if let (Some(line), Some(offset)) = (message["line"].as_usize(), message["offset"].as_usize()) {
+					sender.input(AppMsg::JumpToSentence { line, offset });
+				}
+			});
+		}
+		let analysis_view = &webkit6::WebView::builder()
+			.user_content_manager(&analysis_content_manager)
+			.build();
 
-		let model = Self {
+		let mut model = Self {
 			visible_sidebar_page,
 			visible_window_page,
+			editor_mode: EditorMode::Text,
 			is_stat_dialog_visible: false,
 			is_page_empty: true,
 			word_count: 0,
-			is_dark: adw::StyleManager::default().is_dark(),
+			is_dark,
 			ideas,
 			title: "".to_string(),
-			text: "".try_into().unwrap(),
+			activity: vec![],
+			streak: 0,
+			goal_progress: GoalProgress::default(),
+			sprint: None,
+			is_sprinting: false,
+			is_previewing: false,
+			is_focused: false,
+			typography: typography.clone(),
+			style_scheme,
+			document: Document::with_rules(counting_rules),
+			analyzer: Analyzer::builder()
+				.detach_worker(counting_rules)
+				.forward(sender.input_sender(), |output| match output {
+					AnalyzerOutput::Sections(generation, sections) => AppMsg::ChangeSections(generation, sections),
+					AnalyzerOutput::Frequencies(report) => AppMsg::ChangeFrequencies(report),
+					AnalyzerOutput::Rhythm(paragraphs) => AppMsg::ChangeRhythm(paragraphs),
+					AnalyzerOutput::Preview(html) => AppMsg::ShowPreview(html),
+				}),
+			analysis_generation: 0,
 			headings: FactoryVecDeque::builder()
 				.launch(gtk::Box::default())
-				.forward(sender.input_sender(), |_| { AppMsg::Quit }),
-			headings_created: 0,
+				.forward(sender.input_sender(), |output| match output {
+					SectionOutput::ScrollToHere(id) => AppMsg::JumpToSection(id),
+					SectionOutput::ChangeStatus(id, status) => AppMsg::ChangeSectionStatus(id, status),
+					SectionOutput::ChangeTarget(id, target) => AppMsg::ChangeSectionTarget(id, target),
+					SectionOutput::SplitIntoWriting(id) => AppMsg::SplitSection(id),
+				}),
+			cards: FactoryVecDeque::builder()
+				.launch(gtk::FlowBox::default())
+				.forward(sender.input_sender(), |output| match output {
+					CardOutput::JumpToText(id) => AppMsg::JumpToSection(id),
+					CardOutput::Move { from, to } => AppMsg::MoveSection { from, to },
+					CardOutput::ChangeSynopsis(id, synopsis) => AppMsg::ChangeSectionSynopsis(id, synopsis),
+				}),
+			outline: Outline::default(),
+			title_buffer: editor_title_text_buffer.clone(),
+			buffer: text_view_buffer.clone(),
+			text_view: text_view.clone(),
+			stats: vec![],
+			project: None,
+			open_document: None,
+			word_counts: HashMap::new(),
+			binder: FactoryVecDeque::builder()
+				.launch(gtk::Box::default())
+				.forward(sender.input_sender(), |output| match output {
+					BinderRowOutput::Open(id) => AppMsg::OpenDocument(id),
+					BinderRowOutput::Rename(id, title) => AppMsg::RenameDocument(id, title),
+					BinderRowOutput::Move(id, direction) => AppMsg::MoveDocument(id, direction),
+					BinderRowOutput::Delete(id) => AppMsg::ConfirmDeleteDocument(id),
+				}),
+			projects: FactoryVecDeque::builder()
+				.launch(gtk::ListBox::default())
+				.forward(sender.input_sender(), |output| match output {
+					ProjectRowOutput::Open(id) => AppMsg::OpenProject(id),
+				}),
+			writings: FactoryVecDeque::builder()
+				.launch(gtk::ListBox::default())
+				.forward(sender.input_sender(), |output| match output {
+					WritingRowOutput::Open(id) => AppMsg::OpenWriting(id),
+					WritingRowOutput::Develop(id) => AppMsg::DevelopWriting(id),
+				}),
+			section_filter: None,
+			writing_id: writing.id,
+			writing_modified: writing.modified,
+			metadata: writing.metadata,
+			history: History::load(),
+			history_view: history_view.clone(),
+			preview_view: preview_view.clone(),
+			text_scroller: text_scroller.clone(),
+			analysis_view: analysis_view.clone(),
+			highlighted_term: None,
+			sprint_generation: 0,
+			counting_rules,
+			hides_markup: settings.boolean("hide-markup"),
+			cursor_line: 0,
+			focus_scope: FocusScope::from_id(&settings.string("focus-scope")).unwrap_or(FocusScope::Paragraph),
+			typewriter: Typewriter::new(text_view, text_scroller),
+			css_provider: css_provider.clone(),
+			text_style_manager: text_style_manager.clone(),
+			suppress_history: suppress_history.clone(),
+			settings,
             tracker: 0,
 		};
 
+		model.refresh_projects();
+		model.refresh_writings();
+		model.refresh_activity();
+		{
+			let sender = sender.clone();
+			glib::timeout_add_local(AUTOSAVE_INTERVAL, move || {
+				sender.input(AppMsg::Autosave);
+				glib::ControlFlow::Continue
+			});
+		}
+
 		let headings_container = model.headings.widget();
+		let cards_container = model.cards.widget();
+		let binder_container = model.binder.widget();
+		let projects_container = model.projects.widget();
+		let writings_container = model.writings.widget();
+
+		let new_document_box = &gtk::Box::default();
+		for kind in DocumentKind::ALL {
+			let button = gtk::Button::with_label(&kind.label());
+			button.add_css_class("flat");
+			let sender = sender.clone();
+			button.connect_clicked(move |_| {
+				sender.input(AppMsg::CreateDocument(kind));
+			});
+			new_document_box.append(&button);
+		}
+
+		let formatting_container = &{
+			let sender = sender.clone();
+			formatting_panel(move |format| {
+				sender.input(AppMsg::ApplyFormat(format));
+			})
+		};
+
+		// The first line of an edit about to be made, the lines it changes, and
+		// how many lines the buffer had before it.
+		let pending_edit: Rc<Cell<Option<(i32, i32, i32)>>> = Rc::new(Cell::new(None));
+		// Edits made while the depth is above zero come from the user.
+		let user_action_depth: Rc<Cell<u32>> = Rc::new(Cell::new(0));
+		// Whether an input method is composing text, which takes its own keys.
+		let is_composing: Rc<Cell<bool>> = Rc::new(Cell::new(false));
+
+		let section_filter_labels: Vec<String> = std::iter::once(i18n("All Statuses"))
+			.chain(SectionStatus::ALL.iter().map(SectionStatus::label))
+			.collect();
 
 		let widgets = view_output!();
 
+		set_text_irreversibly(&widgets.editor_title_text_buffer, &writing.title);
+		set_text_irreversibly(&widgets.text_view_buffer, &writing.text);
+		model.restore_outline(writing.outline);
+
+		{
+			let sender = sender.clone();
+			text_scroller.vadjustment().connect_value_changed(move |_| {
+				sender.input(AppMsg::SyncPreview);
+			});
+		}
+
 		let app = main_application();
 
 		let mut actions = RelmActionGroup::<AppActionGroup>::new();
 
 	    let quit_action = {
-	        let app = app.clone();
+	        let sender = sender.clone();
 	        RelmAction::<QuitAction>::new_stateless(move |_| {
-	            app.quit();
+	            sender.input(AppMsg::Quit);
 	        })
 	    };
 	    actions.add_action(quit_action);
@@ -641,51 +2520,505 @@ impl SimpleComponent for App {
 		actions.add_action(delete_action);
 		app.set_accelerators_for_action::<DeleteAction>(&["<Control>d"]);
 
+		let formatting_action = {
+			let sender = sender.clone();
+			RelmAction::<FormattingAction>::new_stateless(move |_| {
+				sender.input(AppMsg::SwitchSidebarPage(SidebarPage::Formatting));
+			})
+		};
+		actions.add_action(formatting_action);
+
+		let format_action = {
+			let sender = sender.clone();
+			RelmAction::<FormatAction>::new_with_target_value(move |_, id: String| {
+				if let Some(format) = Format::from_id(&id) {
+					sender.input(AppMsg::ApplyFormat(format));
+				}
+			})
+		};
+		actions.add_action(format_action);
+		// Taken by the text alone, leaving the title and search fields their
+		// own shortcuts.
+		let format_shortcuts = gtk::ShortcutController::new();
+		for format in Format::ALL {
+			let shortcut = gtk::Shortcut::builder()
+				.trigger(&gtk::ShortcutTrigger::parse_string(&format.accelerator()).expect("Invalid format accelerator"))
+				.action(&gtk::NamedAction::new("app.format"))
+				.arguments(&format.id().to_variant())
+				.build();
+			format_shortcuts.add_shortcut(shortcut);
+		}
+		text_view.add_controller(format_shortcuts);
+
+		let preferences_action = {
+			let sender = sender.clone();
+			RelmAction::<PreferencesAction>::new_stateless(move |_| {
+				sender.input(AppMsg::ShowPreferences);
+			})
+		};
+		actions.add_action(preferences_action);
+		app.set_accelerators_for_action::<PreferencesAction>(&["<Control>comma"]);
+
+		let zoom_in_action = {
+			let settings = model.settings.clone();
+			RelmAction::<ZoomInAction>::new_stateless(move |_| {
+				typography::zoom(&settings, Some(1));
+			})
+		};
+		actions.add_action(zoom_in_action);
+		app.set_accelerators_for_action::<ZoomInAction>(&["<Control>plus", "<Control>equal", "<Control>KP_Add"]);
+
+		let zoom_out_action = {
+			let settings = model.settings.clone();
+			RelmAction::<ZoomOutAction>::new_stateless(move |_| {
+				typography::zoom(&settings, Some(-1));
+			})
+		};
+		actions.add_action(zoom_out_action);
+		app.set_accelerators_for_action::<ZoomOutAction>(&["<Control>minus", "<Control>KP_Subtract"]);
+
+		let zoom_reset_action = {
+			let settings = model.settings.clone();
+			RelmAction::<ZoomResetAction>::new_stateless(move |_| {
+				typography::zoom(&settings, None);
+			})
+		};
+		actions.add_action(zoom_reset_action);
+		app.set_accelerators_for_action::<ZoomResetAction>(&["<Control>0", "<Control>KP_0"]);
+
+		let focus_mode_action = {
+			let sender = sender.clone();
+			RelmAction::<FocusModeAction>::new_stateless(move |_| {
+				sender.input(AppMsg::ToggleFocusMode);
+			})
+		};
+		actions.add_action(focus_mode_action);
+		app.set_accelerators_for_action::<FocusModeAction>(&["<Control><Shift>f"]);
+
+		let convert_punctuation_action = {
+			let sender = sender.clone();
+			RelmAction::<ConvertPunctuationAction>::new_stateless(move |_| {
+				sender.input(AppMsg::ConvertPunctuation);
+			})
+		};
+		actions.add_action(convert_punctuation_action);
+
+		let language_action = {
+			let sender = sender.clone();
+			RelmAction::<LanguageAction>::new_stateful_with_target_value(&model.metadata.language.clone().unwrap_or_default(), move |_, state, language: String| {
+				*state = language.clone();
+				sender.input(AppMsg::ChangeLanguage(language));
+			})
+		};
+		actions.add_action(language_action);
+
 	    actions.register_for_main_application();
+		app.add_action(&model.settings.create_action("hide-markup"));
+		app.add_action(&model.settings.create_action("smart-punctuation"));
+		app.add_action(&model.settings.create_action("focus-scope"));
+		app.add_action(&model.settings.create_action("typewriter-scrolling"));
+		model.typewriter.set_enabled(model.settings.boolean("typewriter-scrolling"), model.settings.double("typewriter-position"));
 
 		ComponentParts { model, widgets }
 	}
 
-	fn update(&mut self, message: Self::Input, _sender: ComponentSender<Self>) {
+	fn update(&mut self, message: Self::Input, sender: ComponentSender<Self>) {
 		self.reset();
 
 		match message {
-			AppMsg::Quit => main_application().quit(),
+			AppMsg::NewProject => {
+				let mut project = Project::new(i18n("Untitled Project"));
+				project.items.push(BinderItem::new(DocumentKind::Chapter, i18n("Chapter"), String::new()));
+				self.open_project(project);
+				if let Err(err) = self.save() {
+					eprintln!("Could not save project: {}", err);
+				}
+				self.refresh_projects();
+			},
+			AppMsg::OpenProject(id) => {
+				if let Some(project) = Project::load_by_id(&id) {
+					self.open_project(project);
+				}
+			},
+			AppMsg::OpenWriting(id) => {
+				self.open_writing(id);
+			},
+			AppMsg::DevelopWriting(id) => {
+				self.open_writing(id);
+				self.choose_project_template(sender);
+			},
+			AppMsg::OpenDocument(id) => {
+				self.open_document(id);
+			},
+			AppMsg::CreateDocument(kind) => {
+				self.create_document(kind);
+			},
+			AppMsg::RenameDocument(id, title) => {
+				if let Some(item) = self.project.as_mut().and_then(|project| project.find_mut(&id)) {
+					item.title = title;
+				}
+				self.refresh_binder();
+			},
+			AppMsg::MoveDocument(id, direction) => {
+				if let Some(project) = &mut self.project {
+					project.move_item(&id, direction);
+				}
+				self.refresh_binder();
+			},
+			AppMsg::ConfirmDeleteDocument(id) => {
+				self.confirm_delete_document(id, sender);
+			},
+			AppMsg::DeleteDocument(id) => {
+				if let Some(project) = &mut self.project {
+					project.remove(&id);
+				}
+				let is_open_deleted = self.open_document.as_ref().is_some_and(|open| {
+					self.project.as_ref().is_some_and(|project| project.find(open).is_none())
+				});
+				if is_open_deleted {
+					// The deleted document, or its parent, was open: nothing to sync back.
+					self.open_document = None;
+					let next = self.project.as_ref()
+						.and_then(|project| project.items.first())
+						.map(|item| item.id.clone());
+					match next {
+						Some(id) => self.open_document(id),
+						None => self.create_document(DocumentKind::Chapter),
+					}
+				}
+				self.refresh_binder();
+			},
+			AppMsg::CompileProject => {
+				self.compile_project();
+			},
+			AppMsg::SplitSection(id) => {
+				self.split_section(id);
+			},
+			AppMsg::ChooseMergeTarget => {
+				self.choose_merge_target(sender);
+			},
+			AppMsg::MergeInto(id) => {
+				self.merge_into(id);
+			},
+			AppMsg::ChooseProjectTemplate => {
+				self.choose_project_template(sender);
+			},
+			AppMsg::DevelopIntoProject(template) => {
+				self.develop_into_project(template);
+			},
+			AppMsg::ChooseTemplate => {
+				self.choose_template(sender);
+			},
+			AppMsg::NewWriting(title, template) => {
+				self.new_writing(title, template);
+			},
+			AppMsg::ImportTemplate => {
+				let dialog = gtk::FileDialog::builder()
+					.title(i18n("Import Template"))
+					.modal(true)
+					.build();
+				dialog.open(main_application().active_window().as_ref(), None::<&gio::Cancellable>, move |result| {
+					let Some(path) = result.ok().and_then(|file| file.path()) else {
+						return;
+					};
+					if let Err(err) = Template::import(&path) {
+						eprintln!("Could not import template: {}", err);
+					}
+					sender.input(AppMsg::ChooseTemplate);
+				});
+			},
+			AppMsg::ExportTemplate(template) => {
+				let dialog = gtk::FileDialog::builder()
+					.title(i18n("Export Template"))
+					.initial_name(format!("{}.md", template.name))
+					.modal(true)
+					.build();
+				dialog.save(main_application().active_window().as_ref(), None::<&gio::Cancellable>, move |result| {
+					let Some(path) = result.ok().and_then(|file| file.path()) else {
+						return;
+					};
+					if let Err(err) = template.export(&path) {
+						eprintln!("Could not export template: {}", err);
+					}
+				});
+			},
+			AppMsg::Autosave => {
+				if let Err(err) = self.save() {
+					eprintln!("Could not save writing: {}", err);
+				}
+			},
+			AppMsg::Quit => {
+				if let Err(err) = self.save() {
+					eprintln!("Could not save writing: {}", err);
+				}
+				main_application().quit();
+			},
+			AppMsg::RefreshHistory => {
+				self.refresh_history_chart();
+			},
+			AppMsg::ExportHistory => {
+				self.export_history();
+			},
+			AppMsg::ChangeGoal(goal) => {
+				self.metadata.goal = goal;
+				self.refresh_goal();
+			},
+			AppMsg::ChangeSettings => {
+				let rules = load_counting_rules(&self.settings);
+				if rules != self.counting_rules {
+					self.counting_rules = rules;
+					self.document.set_rules(rules);
+					self.analyzer.emit(AnalyzerMsg::SetRules(rules));
+					self.analyzer.emit(AnalyzerMsg::Analyze(self.analysis_generation));
+					self.set_word_count(self.document.word_count());
+					self.count_project_words();
+					self.refresh_goal();
+				}
+				let focus_scope = FocusScope::from_id(&self.settings.string("focus-scope")).unwrap_or(FocusScope::Paragraph);
+				if focus_scope != self.focus_scope {
+					self.focus_scope = focus_scope;
+					if self.is_focused {
+						focus::focus(&self.buffer, focus_scope);
+					}
+				}
+				self.typewriter.set_enabled(self.settings.boolean("typewriter-scrolling"), self.settings.double("typewriter-position"));
+				let typography = Typography::load(&self.settings);
+				self.css_provider.load_from_string(&format!("{}\n{}", theme::accent_css(&self.settings), typography.css()));
+				if typography != self.typography {
+					self.set_typography(typography);
+					self.refresh_preview();
+				}
+				self.refresh_style_scheme();
+				let hides_markup = self.settings.boolean("hide-markup");
+				if hides_markup != self.hides_markup {
+					self.hides_markup = hides_markup;
+					if hides_markup {
+						wysiwym::render_all(self.buffer.upcast_ref(), self.cursor_line);
+					} else {
+						wysiwym::clear(self.buffer.upcast_ref());
+					}
+				}
+			},
+			AppMsg::RefreshAnalysis => {
+				self.refresh_analysis();
+			},
+			AppMsg::ChangeFrequencies(report) => {
+				let script = format!("window.austeur.setReport({})", report.to_json());
+				self.analysis_view.evaluate_javascript(&script, None, None, None::<&gio::Cancellable>, |result| {
+					if let Err(err) = result {
+						eprintln!("Could not chart frequencies: {}", err);
+					}
+				});
+			},
+			AppMsg::Punctuate { offset, typed } => {
+				if self.settings.boolean("smart-punctuation") {
+					self.punctuate(offset, typed);
+				}
+			},
+			AppMsg::ConvertPunctuation => {
+				if self.visible_window_page == WindowPage::Editor && self.editor_mode == EditorMode::Text {
+					self.convert_punctuation();
+				}
+			},
+			AppMsg::ChangeLanguage(language) => {
+				self.metadata.language = Some(language).filter(|language| !language.is_empty());
+			},
+			AppMsg::ApplyFormat(format) => {
+				if self.visible_window_page == WindowPage::Editor && self.editor_mode == EditorMode::Text {
+					self.edit_quietly(|buffer| format.toggle(buffer));
+					self.text_view.grab_focus();
+				}
+			},
+			AppMsg::HighlightTerm(term) => {
+				self.highlight_term(term);
+			},
+			AppMsg::ChangeRhythm(paragraphs) => {
+				let script = format!("window.austeur.setRhythm({})", rhythm::to_json(&paragraphs));
+				self.analysis_view.evaluate_javascript(&script, None, None, None::<&gio::Cancellable>, |result| {
+					if let Err(err) = result {
+						eprintln!("Could not chart rhythm: {}", err);
+					}
+				});
+			},
+			AppMsg::TogglePreview(is_previewing) => {
+				self.set_is_previewing(is_previewing);
+				self.refresh_preview();
+			},
+			AppMsg::RefreshPreview => {
+				self.refresh_preview();
+			},
+			AppMsg::ShowPreview(html) => {
+				self.show_preview(&html);
+			},
+			AppMsg::SyncPreview => {
+				self.sync_preview();
+			},
+			AppMsg::ShowPreferences => {
+				if let Some(window) = main_application().active_window() {
+					let dialog = typography::preferences_dialog(&self.settings);
+					let sender = sender.clone();
+					dialog.add(&theme::appearance_page(&self.settings, &self.text_style_manager, move || {
+						sender.input(AppMsg::RefreshStyleScheme);
+					}));
+					dialog.present(&window);
+				}
+			},
+			AppMsg::ToggleFocusMode => {
+				if self.is_focused {
+					self.set_is_focused(false);
+					focus::clear(&self.buffer);
+				} else if self.visible_window_page == WindowPage::Editor {
+					self.set_is_focused(true);
+					self.set_editor_mode(EditorMode::Text);
+					focus::focus(&self.buffer, self.focus_scope);
+					self.text_view.grab_focus();
+				}
+			},
+			AppMsg::JumpToSentence { line, offset } => {
+				self.jump_to_sentence(line, offset);
+			},
+			AppMsg::ChooseSprint => {
+				self.choose_sprint(sender);
+			},
+			AppMsg::StartSprint { minutes, target } => {
+				self.start_sprint(minutes, target, sender);
+			},
+			AppMsg::SprintTick(generation) => {
+				if generation != self.sprint_generation {
+					return;
+				}
+				let word_count = self.total_word_count();
+				let is_over = self.get_mut_sprint().as_mut().is_some_and(|sprint| sprint.tick(word_count));
+				if is_over {
+					self.finish_sprint();
+				} else {
+					glib::timeout_add_local_once(SPRINT_TICK, move || {
+						sender.input(AppMsg::SprintTick(generation));
+					});
+				}
+			},
+			AppMsg::StopSprint => {
+				self.finish_sprint();
+			},
 			AppMsg::SwitchSidebarPage(page) => {
+				match page {
+					SidebarPage::History => self.refresh_history_chart(),
+					SidebarPage::Analysis => self.refresh_analysis(),
+					_ => (),
+				}
 				self.set_visible_sidebar_page(page);
 			},
 			AppMsg::SwitchWindowPage(page) => {
+				if page == WindowPage::Home {
+					// Saved first, so that the writing being edited is listed as it is.
+					if let Err(err) = self.save() {
+						eprintln!("Could not save writing: {}", err);
+					}
+					self.refresh_writings();
+					self.refresh_activity();
+				}
 				self.set_visible_window_page(page);
 			},
+			AppMsg::SwitchEditorMode(mode) => {
+				self.set_editor_mode(mode);
+			},
 			AppMsg::ChangeTheme(is_dark) => {
 				self.set_is_dark(is_dark);
+				self.refresh_style_scheme();
 			},
-			AppMsg::ChangeText(text) => {
-				let words_count::WordsCount { words , .. } = words_count::count(text.clone());
-				self.set_word_count(words);
+			AppMsg::RefreshStyleScheme => {
+				// The scheme was loaded again, so set it even if its id is the same.
+				self.get_mut_style_scheme();
+			},
+			AppMsg::ChangeLines { start, removed, lines, typed } => {
+				self.analyzer.emit(AnalyzerMsg::Splice { start, removed, lines: lines.clone() });
+				let previous_word_count = self.document.word_count();
+				if self.hides_markup {
+					wysiwym::render(self.buffer.upcast_ref(), start as i32..(start + lines.len()) as i32, self.cursor_line);
+				}
+				if self.is_focused {
+					focus::dim_lines(&self.buffer, start as i32..(start + lines.len()) as i32);
+					focus::focus(&self.buffer, self.focus_scope);
+				}
+				self.document.splice(start, removed, lines);
+				if typed {
+					self.writing_modified = store::now();
+					let delta = self.document.word_count() as isize - previous_word_count as isize;
+					self.history.record(&self.history_id(), delta);
+				}
+				self.set_word_count(self.document.word_count());
 				self.set_is_page_empty(*self.get_word_count() <= 0);
-				let text_a = text.as_str();
-				let headings_raw: Vec<String> = strip_headings(text_a).into_iter().map(|x: &str| x.to_owned()).collect();
-				println!("headings_raw: {}", {
-					let headings_raw = headings_raw.clone();
-					headings_raw.into_iter().fold("".to_string(), |acc: String, x: String| acc + &x)
+
+				// Wait for a pause in typing before doing the heavier work.
+				self.analysis_generation += 1;
+				let generation = self.analysis_generation;
+				glib::timeout_add_local_once(ANALYSIS_DELAY, move || {
+					sender.input(AppMsg::Analyze(generation));
 				});
-				self.headings.guard().clear();
-				self.set_headings_created(0);
-				for i in 0..headings_raw.len() {
-					let index = *self.get_headings_created();
-					let name = &headings_raw[i];
-					self.headings.guard().push_back((index, name.to_string()));
-					self.set_headings_created(self.get_headings_created().wrapping_add(1));
+			},
+			AppMsg::Analyze(generation) => {
+				if generation == self.analysis_generation {
+					self.analyzer.emit(AnalyzerMsg::Analyze(generation));
+					self.refresh_goal();
+					self.refresh_preview();
+					match self.visible_sidebar_page {
+						SidebarPage::History => self.refresh_history_chart(),
+						SidebarPage::Analysis => self.refresh_analysis(),
+						_ => (),
+					}
+				}
+			},
+			AppMsg::MoveCursor(line) => {
+				if self.is_focused {
+					focus::focus(&self.buffer, self.focus_scope);
+				}
+				self.typewriter.recenter();
+				if line != self.cursor_line {
+					let previous = std::mem::replace(&mut self.cursor_line, line);
+					if self.hides_markup {
+						for line in [previous, line] {
+							wysiwym::render(self.buffer.upcast_ref(), line..line + 1, self.cursor_line);
+						}
+					}
+				}
+			},
+			AppMsg::ChangeSections(generation, sections) => {
+				// Otherwise the text was edited since, and another analysis is on its way.
+				if generation == self.analysis_generation {
+					self.refresh_headings(sections);
 				}
-				self.set_text(text);
 			},
 			AppMsg::ChangeTitle(text) => {
 				self.set_title(text);
 			},
 			AppMsg::ToggleStatDialog => {
+				if !self.is_stat_dialog_visible {
+					self.stats = self.compute_stats();
+				}
 				self.set_is_stat_dialog_visible(!self.get_is_stat_dialog_visible());
 			},
+			AppMsg::ChangeSectionStatus(id, status) => {
+				self.metadata.section_mut(id).status = status;
+				self.refresh_section(id);
+			},
+			AppMsg::ChangeSectionTarget(id, target) => {
+				self.metadata.section_mut(id).target = target;
+				self.refresh_section(id);
+			},
+			AppMsg::ChangeSectionSynopsis(id, synopsis) => {
+				self.metadata.section_mut(id).synopsis = Some(synopsis).filter(|x| !x.is_empty());
+				self.refresh_section(id);
+			},
+			AppMsg::JumpToSection(id) => {
+				self.jump_to_section(id);
+			},
+			AppMsg::MoveSection { from, to } => {
+				self.move_section(from, to);
+			},
+			AppMsg::FilterSections(filter) => {
+				self.set_section_filter(filter);
+				self.refresh_section_visibility();
+			},
 		}
 	}
 
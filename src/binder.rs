@@ -0,0 +1,297 @@
+use crate::{
+	i18n::{
+		i18n, ni18n_f,
+	},
+	store::{
+		BinderMove, DocumentKind,
+	},
+};
+
+use relm4::{
+	prelude::*,
+	adw, adw::prelude::*,
+	factory, factory::{ FactoryComponent },
+	gtk, gtk::prelude::*,
+};
+
+/// A document of the open project, as a row of the binder.
+#[derive(Debug)]
+pub(super) struct BinderRow {
+	id: String,
+	title: String,
+	kind: DocumentKind,
+	depth: usize,
+	is_open: bool,
+}
+
+#[derive(Debug)]
+pub(super) enum BinderRowMessage {
+
+}
+
+#[derive(Debug)]
+pub(super) enum BinderRowOutput {
+	Open(String),
+	Rename(String, String),
+	Move(String, BinderMove),
+	Delete(String),
+}
+
+#[factory(pub)]
+impl FactoryComponent for BinderRow {
+	type Init = (String, String, DocumentKind, usize, bool);
+	type Input = BinderRowMessage;
+	type Output = BinderRowOutput;
+	type CommandOutput = ();
+	type ParentWidget = gtk::Box;
+
+	view! {
+		root = gtk::Box {
+			set_spacing: 6,
+			set_margin_start: 12 * self.depth as i32,
+
+			gtk::ToggleButton {
+				add_css_class: "flat",
+				set_hexpand: true,
+				set_active: self.is_open,
+
+				connect_clicked[sender, id = self.id.clone()] => move |_| {
+					sender.output(BinderRowOutput::Open(id.clone())).unwrap();
+				},
+
+				gtk::Box {
+					set_spacing: 6,
+
+					gtk::Image {
+						set_icon_name: Some(self.kind.icon_name()),
+						set_tooltip_text: Some(&self.kind.label()),
+					},
+
+					gtk::Label {
+						set_label: &self.title,
+						set_xalign: 0.0,
+						set_ellipsize: gtk::pango::EllipsizeMode::End,
+					},
+				},
+			},
+
+			gtk::MenuButton {
+				add_css_class: "flat",
+				set_icon_name: "view-more-symbolic",
+				set_tooltip_text: Some(&i18n("Document Options")),
+
+				#[wrap(Some)]
+				set_popover = &gtk::Popover {
+					gtk::Box {
+						set_orientation: gtk::Orientation::Vertical,
+						set_spacing: 6,
+
+						gtk::Entry {
+							set_text: &self.title,
+							set_placeholder_text: Some(&i18n("Title")),
+
+							connect_activate[sender, id = self.id.clone()] => move |entry| {
+								sender.output(BinderRowOutput::Rename(id.clone(), entry.text().to_string())).unwrap();
+							},
+						},
+
+						gtk::Box {
+							add_css_class: "linked",
+							set_homogeneous: true,
+
+							gtk::Button {
+								set_icon_name: "go-up-symbolic",
+								set_tooltip_text: Some(&i18n("Move Up")),
+								connect_clicked[sender, id = self.id.clone()] => move |_| {
+									sender.output(BinderRowOutput::Move(id.clone(), BinderMove::Up)).unwrap();
+								},
+							},
+
+							gtk::Button {
+								set_icon_name: "go-down-symbolic",
+								set_tooltip_text: Some(&i18n("Move Down")),
+								connect_clicked[sender, id = self.id.clone()] => move |_| {
+									sender.output(BinderRowOutput::Move(id.clone(), BinderMove::Down)).unwrap();
+								},
+							},
+
+							gtk::Button {
+								set_icon_name: "go-previous-symbolic",
+								set_tooltip_text: Some(&i18n("Outdent")),
+								connect_clicked[sender, id = self.id.clone()] => move |_| {
+									sender.output(BinderRowOutput::Move(id.clone(), BinderMove::Outdent)).unwrap();
+								},
+							},
+
+							gtk::Button {
+								set_icon_name: "go-next-symbolic",
+								set_tooltip_text: Some(&i18n("Indent")),
+								connect_clicked[sender, id = self.id.clone()] => move |_| {
+									sender.output(BinderRowOutput::Move(id.clone(), BinderMove::Indent)).unwrap();
+								},
+							},
+						},
+
+						gtk::Button {
+							add_css_class: "destructive-action",
+							set_label: &i18n("Delete"),
+							connect_clicked[sender, id = self.id.clone()] => move |_| {
+								sender.output(BinderRowOutput::Delete(id.clone())).unwrap();
+							},
+						},
+					},
+				},
+			},
+		}
+	}
+
+	fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+		let (id, title, kind, depth, is_open) = value;
+		Self {
+			id,
+			title,
+			kind,
+			depth,
+			is_open,
+		}
+	}
+}
+
+/// A stored project, as a row on the Home page.
+#[derive(Debug)]
+pub(super) struct ProjectRow {
+	id: String,
+	title: String,
+	document_count: usize,
+}
+
+impl ProjectRow {
+	fn display_title(&self) -> String {
+		if self.title.is_empty() {
+			i18n("Untitled Project")
+		} else {
+			self.title.clone()
+		}
+	}
+}
+
+#[derive(Debug)]
+pub(super) enum ProjectRowMessage {
+
+}
+
+#[derive(Debug)]
+pub(super) enum ProjectRowOutput {
+	Open(String),
+}
+
+#[factory(pub)]
+impl FactoryComponent for ProjectRow {
+	type Init = (String, String, usize);
+	type Input = ProjectRowMessage;
+	type Output = ProjectRowOutput;
+	type CommandOutput = ();
+	type ParentWidget = gtk::ListBox;
+
+	view! {
+		root = adw::ActionRow {
+			set_title: &self.display_title(),
+			set_subtitle: &ni18n_f("{count} document", "{count} documents", self.document_count, &[("count", &self.document_count.to_string())]),
+			set_activatable: true,
+
+			connect_activated[sender, id = self.id.clone()] => move |_| {
+				sender.output(ProjectRowOutput::Open(id.clone())).unwrap();
+			},
+
+			add_suffix = &gtk::Image {
+				set_icon_name: Some("go-next-symbolic"),
+				add_css_class: "dim-label",
+			},
+		}
+	}
+
+	fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+		let (id, title, document_count) = value;
+		Self {
+			id,
+			title,
+			document_count,
+		}
+	}
+}
+
+/// A stored writing, as a row on the Home page.
+#[derive(Debug)]
+pub(super) struct WritingRow {
+	id: String,
+	title: String,
+	excerpt: String,
+}
+
+impl WritingRow {
+	fn display_title(&self) -> String {
+		if self.title.is_empty() {
+			i18n("Untitled")
+		} else {
+			self.title.clone()
+		}
+	}
+}
+
+#[derive(Debug)]
+pub(super) enum WritingRowMessage {
+
+}
+
+#[derive(Debug)]
+pub(super) enum WritingRowOutput {
+	Open(String),
+	Develop(String),
+}
+
+#[factory(pub)]
+impl FactoryComponent for WritingRow {
+	type Init = (String, String, String);
+	type Input = WritingRowMessage;
+	type Output = WritingRowOutput;
+	type CommandOutput = ();
+	type ParentWidget = gtk::ListBox;
+
+	view! {
+		root = adw::ActionRow {
+			set_title: &self.display_title(),
+			set_subtitle: &self.excerpt,
+			set_subtitle_lines: 2,
+			set_activatable: true,
+
+			connect_activated[sender, id = self.id.clone()] => move |_| {
+				sender.output(WritingRowOutput::Open(id.clone())).unwrap();
+			},
+
+			add_suffix = &gtk::Button {
+				set_valign: gtk::Align::Center,
+				set_icon_name: "folder-new-symbolic",
+				set_tooltip_text: Some(&i18n("Develop into Project")),
+				add_css_class: "flat",
+
+				connect_clicked[sender, id = self.id.clone()] => move |_| {
+					sender.output(WritingRowOutput::Develop(id.clone())).unwrap();
+				},
+			},
+
+			add_suffix = &gtk::Image {
+				set_icon_name: Some("go-next-symbolic"),
+				add_css_class: "dim-label",
+			},
+		}
+	}
+
+	fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+		let (id, title, excerpt) = value;
+		Self {
+			id,
+			title,
+			excerpt,
+		}
+	}
+}
@@ -0,0 +1,317 @@
+use relm4::{
+	gtk, gtk::prelude::*, gtk::{gdk, glib},
+};
+
+lazy_static::lazy_static! {
+	static ref ITEM_PATTERN: regex::Regex = regex::Regex::new(
+		r"^(?P<quote>(?:[ \t]*>[ \t]?)*)(?P<indent>[ \t]*)(?:(?P<bullet>[-*+][ \t]+)(?P<task>\[[ xX]\][ \t]+)?|(?P<number>\d{1,9})(?P<delimiter>[.)])(?P<spacing>[ \t]+))?"
+	).unwrap();
+	static ref DIALOGUE_PATTERN: regex::Regex = regex::Regex::new(r"^([A-Z][A-Z0-9 .'’-]+?)(?:[ \t]*\([^)]*\))?:[ \t]+").unwrap();
+	static ref RULE_PATTERN: regex::Regex = regex::Regex::new(r"^[ \t]*(?:(?:-[ \t]*){3,}|(?:\*[ \t]*){3,}|(?:_[ \t]*){3,})$").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+	Quote,
+	/// The marker along with the spacing after it, such as `- ` or `* [ ] `.
+	Bullet(String),
+	Ordered {
+		number: usize,
+		digits: usize,
+		delimiter: char,
+		spacing: String,
+	},
+	/// A line of a script, as in `HAMLET: To be, or not to be`.
+	Dialogue(String),
+}
+
+/// The structure a line is part of, as told by the markers it starts with.
+#[derive(Debug, Clone)]
+struct Structure {
+	/// Block quote markers, along with the indentation around them.
+	quote: String,
+	/// Indentation of the list item, after the block quote markers.
+	indent: usize,
+	kind: Kind,
+	/// Characters taken by all of the markers.
+	len: usize,
+	/// Characters before the text of a list item, leaving out the box of a
+	/// task, which its nested items are aligned with.
+	content: usize,
+}
+
+impl Structure {
+	fn parse(text: &str) -> Option<Self> {
+		if RULE_PATTERN.is_match(text) {
+			return None;
+		}
+		if let Some(captures) = DIALOGUE_PATTERN.captures(text) {
+			return Some(Self {
+				quote: String::new(),
+				indent: 0,
+				kind: Kind::Dialogue(captures[1].trim().to_string()),
+				len: captures[0].chars().count(),
+				content: 0,
+			});
+		}
+		let captures = ITEM_PATTERN.captures(text)?;
+		let quote = captures["quote"].to_string();
+		let kind = if let Some(bullet) = captures.name("bullet") {
+			// A task starts out unchecked.
+			let task = if captures.name("task").is_some() { "[ ] " } else { "" };
+			Kind::Bullet(format!("{}{}", bullet.as_str(), task))
+		} else if let Some(number) = captures.name("number") {
+			Kind::Ordered {
+				number: number.as_str().parse().ok()?,
+				digits: number.len(),
+				delimiter: captures["delimiter"].chars().next()?,
+				spacing: captures["spacing"].to_string(),
+			}
+		} else if !quote.is_empty() {
+			Kind::Quote
+		} else {
+			return None;
+		};
+		let content = captures.name("bullet").or(captures.name("spacing")).map_or(0, |marker| line_chars(text, marker.end()));
+		Some(Self {
+			indent: captures["indent"].chars().count(),
+			len: captures[0].chars().count(),
+			content,
+			quote,
+			kind,
+		})
+	}
+
+	fn is_list_item(&self) -> bool {
+		matches!(self.kind, Kind::Bullet(_) | Kind::Ordered { .. })
+	}
+
+	/// Characters before the list marker.
+	fn marker_start(&self) -> usize {
+		self.quote.chars().count() + self.indent
+	}
+
+	/// Whether `other` is a list item within the same block quote.
+	fn is_in_same_quote(&self, other: &Structure) -> bool {
+		other.is_list_item() && other.quote.trim() == self.quote.trim()
+	}
+}
+
+/// Characters of `text` up to the byte `end`.
+fn line_chars(text: &str, end: usize) -> usize {
+	text[..end].chars().count()
+}
+
+fn line_text(buffer: &gtk::TextBuffer, line: i32) -> Option<String> {
+	let start = buffer.iter_at_line(line)?;
+	let mut end = start.clone();
+	if !end.ends_line() {
+		end.forward_to_line_end();
+	}
+	Some(buffer.text(&start, &end, true).to_string())
+}
+
+fn parse_line(buffer: &gtk::TextBuffer, line: i32) -> Option<Structure> {
+	Structure::parse(&line_text(buffer, line)?)
+}
+
+/// Replaces the characters `range` of `line` with `text`.
+fn replace(buffer: &gtk::TextBuffer, line: i32, range: std::ops::Range<usize>, text: &str) {
+	let (Some(mut start), Some(mut end)) = (buffer.iter_at_line_offset(line, range.start as i32), buffer.iter_at_line_offset(line, range.end as i32)) else {
+		return;
+	};
+	buffer.delete(&mut start, &mut end);
+	buffer.insert(&mut start, text);
+}
+
+/// The speaker before the one of `line` in the exchange it is part of.
+fn previous_speaker(buffer: &gtk::TextBuffer, line: i32, speaker: &str) -> Option<String> {
+	(0..line).rev()
+		.map(|line| parse_line(buffer, line).map(|structure| structure.kind))
+		.take_while(|kind| matches!(kind, Some(Kind::Dialogue(_))))
+		.find_map(|kind| match kind {
+			Some(Kind::Dialogue(other)) if other != speaker => Some(other),
+			_ => None,
+		})
+}
+
+/// Numbers the ordered list that `line` is an item of from its first item on,
+/// leaving out the lists nested in it.
+fn renumber(buffer: &gtk::TextBuffer, line: i32) {
+	let Some(item) = parse_line(buffer, line).filter(|item| matches!(item.kind, Kind::Ordered { .. })) else {
+		return;
+	};
+	let mut first = line;
+	let mut cursor = line;
+	while cursor > 0 {
+		match parse_line(buffer, cursor - 1) {
+			Some(other) if item.is_in_same_quote(&other) && other.indent > item.indent => {},
+			Some(other) if item.is_in_same_quote(&other) && other.indent == item.indent && matches!(other.kind, Kind::Ordered { .. }) => first = cursor - 1,
+			_ => break,
+		}
+		cursor -= 1;
+	}
+
+	let mut next_number = None;
+	for line in first..buffer.line_count() {
+		let Some(other) = parse_line(buffer, line).filter(|other| item.is_in_same_quote(other)) else {
+			break;
+		};
+		if other.indent > item.indent {
+			continue;
+		}
+		let Kind::Ordered { number, digits, .. } = other.kind else {
+			break;
+		};
+		if other.indent < item.indent {
+			break;
+		}
+		let number_wanted = next_number.unwrap_or(number);
+		if number != number_wanted {
+			let start = other.marker_start();
+			replace(buffer, line, start..start + digits, &number_wanted.to_string());
+		}
+		next_number = Some(number_wanted + 1);
+	}
+}
+
+/// Breaks the line at the cursor, carrying on the list, quote or exchange of
+/// dialogue it is part of. On an item left empty, ends it instead. Returns
+/// whether the line was part of any.
+fn continue_line(buffer: &gtk::TextBuffer) -> bool {
+	if buffer.has_selection() {
+		return false;
+	}
+	let cursor = buffer.iter_at_mark(&buffer.get_insert());
+	let line = cursor.line();
+	let Some(text) = line_text(buffer, line) else {
+		return false;
+	};
+	let Some(structure) = Structure::parse(&text) else {
+		return false;
+	};
+	// Within the markers, a line is only broken.
+	if (cursor.line_offset() as usize) < structure.len {
+		return false;
+	}
+
+	buffer.begin_user_action();
+	if text.chars().skip(structure.len).all(char::is_whitespace) {
+		// The block quote is ended along with its last line, and a list
+		// within one along with its marker alone.
+		let start = match structure.kind {
+			Kind::Quote => 0,
+			_ => structure.quote.chars().count(),
+		};
+		replace(buffer, line, start..text.chars().count(), "");
+	} else {
+		let indent: String = text.chars().skip(structure.quote.chars().count()).take(structure.indent).collect();
+		let marker = match &structure.kind {
+			Kind::Quote => String::new(),
+			Kind::Bullet(bullet) => bullet.clone(),
+			Kind::Ordered { number, delimiter, spacing, .. } => format!("{}{}{}", number + 1, delimiter, spacing),
+			Kind::Dialogue(speaker) => previous_speaker(buffer, line, speaker)
+				.map(|speaker| format!("{}: ", speaker))
+				.unwrap_or_default(),
+		};
+		buffer.insert_at_cursor(&format!("\n{}{}{}", structure.quote, indent, marker));
+		if matches!(structure.kind, Kind::Ordered { .. }) {
+			renumber(buffer, line + 1);
+		}
+	}
+	buffer.end_user_action();
+	true
+}
+
+/// Indentation that makes the item of `line` a child of the item before it.
+fn nested_indent(buffer: &gtk::TextBuffer, line: i32, item: &Structure) -> Option<usize> {
+	for line in (0..line).rev() {
+		let other = parse_line(buffer, line).filter(|other| item.is_in_same_quote(other))?;
+		if other.indent == item.indent {
+			return Some(other.content - other.quote.chars().count());
+		}
+		if other.indent < item.indent {
+			return None;
+		}
+	}
+	None
+}
+
+/// Indentation that makes the item of `line` a sibling of its parent.
+fn parent_indent(buffer: &gtk::TextBuffer, line: i32, item: &Structure) -> Option<usize> {
+	if item.indent == 0 {
+		return None;
+	}
+	for line in (0..line).rev() {
+		let Some(other) = parse_line(buffer, line).filter(|other| item.is_in_same_quote(other)) else {
+			break;
+		};
+		if other.indent < item.indent {
+			return Some(other.indent);
+		}
+	}
+	Some(0)
+}
+
+/// Indents or outdents the list items of the selection, or of the line of the
+/// cursor. Returns whether they were all list items, to leave Tab moving the
+/// focus anywhere else.
+fn indent(buffer: &gtk::TextBuffer, outdent: bool) -> bool {
+	let (start, mut end) = buffer.selection_bounds()
+		.unwrap_or_else(|| {
+			let cursor = buffer.iter_at_mark(&buffer.get_insert());
+			(cursor.clone(), cursor)
+		});
+	// A selection up to the start of a line leaves that line out.
+	if end.line() > start.line() && end.starts_line() {
+		end.backward_char();
+	}
+	let lines = start.line()..=end.line();
+	let Some(items) = lines.map(|line| parse_line(buffer, line).filter(Structure::is_list_item).map(|item| (line, item))).collect::<Option<Vec<_>>>() else {
+		return false;
+	};
+
+	buffer.begin_user_action();
+	for (line, item) in items {
+		let indent = if outdent { parent_indent(buffer, line, &item) } else { nested_indent(buffer, line, &item) };
+		let Some(indent) = indent else {
+			continue;
+		};
+		let quote = item.quote.chars().count();
+		if let Kind::Ordered { digits, .. } = item.kind {
+			// A new nested list starts at one, then follows the one it joins.
+			if !outdent {
+				replace(buffer, line, item.marker_start()..item.marker_start() + digits, "1");
+			}
+		}
+		replace(buffer, line, quote..quote + item.indent, &" ".repeat(indent));
+		if matches!(item.kind, Kind::Ordered { .. }) {
+			renumber(buffer, line);
+			// The list the item came from.
+			for other in [line - 1, line + 1] {
+				if other >= 0 && other < buffer.line_count() {
+					renumber(buffer, other);
+				}
+			}
+		}
+	}
+	buffer.end_user_action();
+	true
+}
+
+/// Continues lists, quotes and dialogue on Enter, and indents or outdents
+/// list items on Tab and Shift+Tab. Any other key goes to the text view.
+pub(super) fn handle_key(buffer: &gtk::TextBuffer, key: gdk::Key, modifiers: gdk::ModifierType) -> glib::Propagation {
+	let is_plain = !modifiers.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK | gdk::ModifierType::SUPER_MASK);
+	let is_handled = match key {
+		gdk::Key::Return | gdk::Key::KP_Enter | gdk::Key::ISO_Enter if is_plain && !modifiers.contains(gdk::ModifierType::SHIFT_MASK) => continue_line(buffer),
+		gdk::Key::Tab | gdk::Key::KP_Tab if is_plain => indent(buffer, modifiers.contains(gdk::ModifierType::SHIFT_MASK)),
+		gdk::Key::ISO_Left_Tab if is_plain => indent(buffer, true),
+		_ => false,
+	};
+	if is_handled {
+		glib::Propagation::Stop
+	} else {
+		glib::Propagation::Proceed
+	}
+}
@@ -0,0 +1,158 @@
+use crate::{
+	i18n::{
+		i18n, ni18n_f,
+	},
+	store::{
+		SectionMetadata
+	},
+	toc::{
+		OutlineEntry, SectionId,
+	},
+};
+
+use relm4::{
+	prelude::*,
+	factory, factory::{ FactoryComponent },
+	gtk, gtk::prelude::*, gtk::gdk,
+};
+
+/// An index card on the corkboard, standing for one section of the outline.
+#[derive(Debug)]
+pub(super) struct Card {
+	id: SectionId,
+	name: String,
+	word_count: usize,
+	metadata: SectionMetadata,
+}
+
+impl Card {
+	pub(super) fn update(&mut self, entry: OutlineEntry) {
+		self.name = entry.name;
+		self.word_count = entry.word_count;
+	}
+
+	pub(super) fn set_metadata(&mut self, metadata: SectionMetadata) {
+		self.metadata = metadata;
+	}
+}
+
+#[derive(Debug)]
+pub(super) enum CardMessage {
+
+}
+
+#[derive(Debug)]
+pub(super) enum CardOutput {
+	JumpToText(SectionId),
+	Move {
+		from: SectionId,
+		to: SectionId,
+	},
+	ChangeSynopsis(SectionId, String),
+}
+
+#[factory(pub)]
+impl FactoryComponent for Card {
+	type Init = (OutlineEntry, SectionMetadata);
+	type Input = CardMessage;
+	type Output = CardOutput;
+	type CommandOutput = ();
+	type ParentWidget = gtk::FlowBox;
+
+	view! {
+		root = gtk::Box {
+			set_orientation: gtk::Orientation::Vertical,
+			set_spacing: 6,
+			set_width_request: 200,
+			set_height_request: 140,
+			#[watch]
+			set_css_classes: &["card", "index-card", self.metadata.status.css_class()],
+
+			add_controller = gtk::GestureClick {
+				connect_pressed[sender, id = self.id] => move |_, n_press, _, _| {
+					if n_press == 2 {
+						sender.output(CardOutput::JumpToText(id)).unwrap();
+					}
+				},
+			},
+
+			// The payload of a drag is the section of the card being dragged,
+			// since the cards may be reordered before it is dropped.
+			add_controller = gtk::DragSource {
+				set_actions: gdk::DragAction::MOVE,
+				connect_prepare[id = self.id] => move |_, _, _| {
+					Some(gdk::ContentProvider::for_value(&id.to_raw().to_value()))
+				},
+			},
+
+			add_controller = gtk::DropTarget::new(u64::static_type(), gdk::DragAction::MOVE) {
+				connect_drop[sender, to = self.id] => move |_, value, _, _| {
+					let Ok(from) = value.get::<u64>().map(SectionId::from_raw) else {
+						return false;
+					};
+					if from != to {
+						sender.output(CardOutput::Move { from, to }).unwrap();
+					}
+					true
+				},
+			},
+
+			gtk::Label {
+				add_css_class: "heading",
+				set_xalign: 0.0,
+				set_wrap: true,
+				set_lines: 2,
+				set_ellipsize: gtk::pango::EllipsizeMode::End,
+				#[watch]
+				set_label: self.name.trim_start_matches('#').trim(),
+			},
+
+			gtk::TextView {
+				add_css_class: "synopsis",
+				set_vexpand: true,
+				set_wrap_mode: gtk::WrapMode::WordChar,
+				set_tooltip_text: Some(&i18n("Synopsis")),
+
+				#[wrap(Some)]
+				set_buffer = &gtk::TextBuffer {
+					set_text: self.metadata.synopsis.as_deref().unwrap_or_default(),
+
+					connect_changed[sender, id = self.id] => move |buffer| {
+						let (start, end) = buffer.bounds();
+						let synopsis = buffer.text(&start, &end, false);
+						sender.output(CardOutput::ChangeSynopsis(id, synopsis.to_string())).unwrap();
+					},
+				},
+			},
+
+			gtk::Box {
+				set_spacing: 6,
+
+				gtk::Label {
+					add_css_class: "dim-label",
+					add_css_class: "numeric",
+					set_hexpand: true,
+					set_xalign: 0.0,
+					#[watch]
+					set_label: &ni18n_f("{count} word", "{count} words", self.word_count, &[("count", &self.word_count.to_string())]),
+				},
+
+				gtk::Label {
+					add_css_class: "caption",
+					#[watch]
+					set_label: &self.metadata.status.label(),
+				},
+			},
+		}
+	}
+
+	fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+		let (entry, metadata) = value;
+		Self {
+			id: entry.id,
+			name: entry.name,
+			word_count: entry.word_count,
+			metadata,
+		}
+	}
+}
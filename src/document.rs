@@ -0,0 +1,363 @@
+use crate::wordcount::{
+	CountingRules, count_words, words,
+};
+
+/// Plain-text mirror of an editor buffer, kept line by line so that an edit
+/// only costs as much as the lines it touches.
+#[derive(Debug, Clone)]
+pub(super) struct Document {
+	lines: Vec<Line>,
+	word_count: usize,
+	rules: CountingRules,
+}
+
+/// What a line starts in, which decides how much of it is counted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Region {
+	Text,
+	FrontMatter,
+	Comment,
+	/// A fenced code block, with the character of its fence.
+	Code(char),
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+	text: String,
+	/// Words when the line starts outside of a comment.
+	word_count: usize,
+	/// Words when the line starts within a comment, that is after its `-->`.
+	tail_word_count: usize,
+	/// Whether a comment is left open at the end of the line.
+	opens_comment: bool,
+	/// Byte offset of the `-->` closing a comment the line starts in.
+	comment_end: Option<usize>,
+	/// Whether the line could end front matter.
+	is_fence: bool,
+	/// The character of the fence of a code block the line could open or close.
+	code_fence: Option<char>,
+	heading: Option<(usize, usize)>,
+	region: Region,
+}
+
+/// Lines of a text, split at every line break a text buffer knows: `\n`,
+/// `\r\n`, `\r` and the paragraph separator.
+pub(super) fn split_lines(text: &str) -> Vec<&str> {
+	let mut lines = vec![];
+	let mut start = 0;
+	let mut chars = text.char_indices().peekable();
+	while let Some((index, c)) = chars.next() {
+		let end = match c {
+			'\r' if chars.peek().is_some_and(|(_, next)| *next == '\n') => {
+				chars.next();
+				index + 2
+			},
+			'\n' | '\r' | '\u{2029}' => index + c.len_utf8(),
+			_ => continue,
+		};
+		lines.push(&text[start..index]);
+		start = end;
+	}
+	lines.push(&text[start..]);
+	lines
+}
+
+/// The part of a line outside of a comment, along with whether it leaves one open.
+fn outside_comment(text: &str) -> (&str, bool) {
+	let open = text.rfind("<!--");
+	let close = text.rfind("-->");
+	match open {
+		Some(open) if close.map_or(true, |close| close < open) => (&text[..open], true),
+		_ => (text, false),
+	}
+}
+
+impl Line {
+	fn new(text: String, rules: &CountingRules) -> Self {
+		lazy_static::lazy_static! {
+			static ref HEADING_PATTERN: regex::Regex = regex::Regex::new(r"^#{1,6}[ \t]+\S.*").unwrap();
+			static ref CODE_FENCE_PATTERN: regex::Regex = regex::Regex::new(r"^ {0,3}(```|~~~)").unwrap();
+		}
+		let (outside, opens_comment) = outside_comment(&text);
+		let comment_end = text.find("-->");
+		// Markup that is counted makes no difference to where the line starts.
+		let (word_count, tail_word_count) = if rules.exclude_markup {
+			let tail = comment_end.map_or("", |close| outside_comment(&text[close + 3..]).0);
+			(count_words(outside, rules), count_words(tail, rules))
+		} else {
+			let word_count = count_words(&text, rules);
+			(word_count, word_count)
+		};
+		let is_fence = matches!(text.trim_end(), "---" | "...");
+		let code_fence = CODE_FENCE_PATTERN.captures(&text).and_then(|captures| captures[1].chars().next());
+		let heading = HEADING_PATTERN.find(text.as_str()).map(|x| (x.start(), x.end()));
+		Self {
+			text,
+			word_count,
+			tail_word_count,
+			opens_comment,
+			comment_end,
+			is_fence,
+			code_fence,
+			heading,
+			region: Region::Text,
+		}
+	}
+
+	/// Whether the line can change the region of the lines after it.
+	fn is_marker(&self) -> bool {
+		self.is_fence || self.opens_comment || self.comment_end.is_some() || self.code_fence.is_some()
+	}
+
+	/// Whether the line is read as prose, rather than markup or code.
+	fn is_prose(&self) -> bool {
+		self.region == Region::Text && self.code_fence.is_none()
+	}
+
+	/// Whether the line opens front matter, when it is the first one.
+	fn opens_front_matter(&self) -> bool {
+		self.text.trim_end() == "---"
+	}
+
+	fn heading(&self) -> Option<&str> {
+		match self.region {
+			Region::Text => self.heading.map(|(start, end)| &self.text[start..end]),
+			_ => None,
+		}
+	}
+
+	/// Words counted towards the document.
+	fn counted(&self, rules: &CountingRules) -> usize {
+		match self.region {
+			_ if !rules.exclude_markup => self.word_count,
+			Region::Text | Region::Code(_) => self.word_count,
+			Region::FrontMatter => 0,
+			Region::Comment => self.tail_word_count,
+		}
+	}
+
+	/// The text whose words are counted towards the document.
+	fn counted_text(&self, rules: &CountingRules) -> &str {
+		match self.region {
+			_ if !rules.exclude_markup => &self.text,
+			Region::Text | Region::Code(_) => outside_comment(&self.text).0,
+			Region::FrontMatter => "",
+			Region::Comment => self.comment_end.map_or("", |close| outside_comment(&self.text[close + 3..]).0),
+		}
+	}
+
+	/// The region of the next line. Front matter only ever starts on the first
+	/// line, see `Document::rescan`.
+	fn region_after(&self, index: usize) -> Region {
+		match self.region {
+			Region::FrontMatter if self.is_fence && index > 0 => Region::Text,
+			Region::FrontMatter => Region::FrontMatter,
+			Region::Code(fence) if self.code_fence == Some(fence) => Region::Text,
+			Region::Code(fence) => Region::Code(fence),
+			Region::Comment if self.comment_end.is_none() => Region::Comment,
+			_ if self.opens_comment => Region::Comment,
+			_ => self.code_fence.filter(|_| self.region == Region::Text).map_or(Region::Text, Region::Code),
+		}
+	}
+}
+
+impl Default for Document {
+	fn default() -> Self {
+		Self::with_rules(CountingRules::default())
+	}
+}
+
+impl Document {
+	pub(super) fn with_rules(rules: CountingRules) -> Self {
+		// Like a text buffer, an empty document still has one (empty) line.
+		Self {
+			lines: vec![Line::new(String::new(), &rules)],
+			word_count: 0,
+			rules,
+		}
+	}
+
+	pub(super) fn new(text: &str, rules: CountingRules) -> Self {
+		let mut document = Self::with_rules(rules);
+		document.splice(0, 1, split_lines(text).into_iter().map(String::from).collect());
+		document
+	}
+
+	/// Counts the words again following other rules.
+	pub(super) fn set_rules(&mut self, rules: CountingRules) {
+		*self = Self::new(&self.text(), rules);
+	}
+
+	/// Replaces `removed` lines starting at line `start` with `lines`.
+	pub(super) fn splice(&mut self, start: usize, removed: usize, lines: Vec<String>) {
+		let start = start.min(self.lines.len());
+		let end = (start + removed).min(self.lines.len());
+		let region = match start.checked_sub(1) {
+			Some(previous) => self.lines[previous].region_after(previous),
+			None => Region::Text,
+		};
+		let mut inserted: Vec<Line> = lines.into_iter().map(|line| Line::new(line, &self.rules)).collect();
+		let removed_lines: Vec<Line> = self.lines.splice(start..end, std::iter::empty()).collect();
+		// Fences and comment markers can change how every following line is
+		// read, and so can a new first line. Otherwise the new lines are read
+		// like the one before them.
+		if start == 0 || inserted.iter().chain(removed_lines.iter()).any(Line::is_marker) {
+			self.lines.splice(start..start, inserted);
+			if self.lines.is_empty() {
+				self.lines.push(Line::new(String::new(), &self.rules));
+			}
+			self.rescan();
+			return;
+		}
+		for line in inserted.iter_mut() {
+			line.region = region;
+		}
+		let added_words: usize = inserted.iter().map(|line| line.counted(&self.rules)).sum();
+		let removed_words: usize = removed_lines.iter().map(|line| line.counted(&self.rules)).sum();
+		self.lines.splice(start..start, inserted);
+		self.word_count = self.word_count + added_words - removed_words;
+		if self.lines.is_empty() {
+			self.lines.push(Line::new(String::new(), &self.rules));
+		}
+	}
+
+	/// Works out the region of every line, and the word count with it. A
+	/// `---` on the first line only opens front matter once it is closed, so
+	/// that the text does not vanish while it is being written.
+	fn rescan(&mut self) {
+		let has_front_matter = self.lines[0].opens_front_matter() && self.lines[1..].iter().any(|line| line.is_fence);
+		let mut region = if has_front_matter { Region::FrontMatter } else { Region::Text };
+		let mut word_count = 0;
+		for (index, line) in self.lines.iter_mut().enumerate() {
+			line.region = region;
+			word_count += line.counted(&self.rules);
+			region = line.region_after(index);
+		}
+		self.word_count = word_count;
+	}
+
+	pub(super) fn rules(&self) -> CountingRules {
+		self.rules
+	}
+
+	/// Lines that are read as text, leaving out front matter, comments and
+	/// code blocks, with their index.
+	pub(super) fn prose_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+		self.lines.iter().enumerate()
+			.filter(|(_, line)| line.is_prose())
+			.map(|(index, line)| (index, line.text.as_str()))
+	}
+
+	/// Whether text typed after `before`, the start of the line `index`, is
+	/// prose rather than front matter, a comment or code.
+	pub(super) fn is_prose_at(&self, index: usize, before: &str) -> bool {
+		let Some(line) = self.lines.get(index) else {
+			return false;
+		};
+		match line.region {
+			Region::Text => line.code_fence.is_none(),
+			Region::Comment => before.contains("-->"),
+			Region::FrontMatter | Region::Code(_) => false,
+		}
+	}
+
+	/// Lines of Markdown to render, leaving out front matter and comments,
+	/// with their index.
+	pub(super) fn markdown_lines(&self) -> impl Iterator<Item = (usize, &str)> {
+		self.lines.iter().enumerate()
+			.filter(|(_, line)| matches!(line.region, Region::Text | Region::Code(_)))
+			.map(|(index, line)| (index, line.text.as_str()))
+	}
+
+	pub(super) fn word_count(&self) -> usize {
+		self.word_count
+	}
+
+	/// The words counted by `word_count`.
+	pub(super) fn words(&self) -> Vec<String> {
+		self.lines.iter()
+			.flat_map(|line| words(line.counted_text(&self.rules), &self.rules))
+			.collect()
+	}
+
+	pub(super) fn line_count(&self) -> usize {
+		self.lines.len()
+	}
+
+	pub(super) fn is_empty(&self) -> bool {
+		self.lines.iter().all(|line| line.text.is_empty())
+	}
+
+	pub(super) fn text(&self) -> String {
+		self.lines.iter()
+			.map(|line| line.text.as_str())
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+
+	/// The first few lines, for previews.
+	pub(super) fn excerpt(&self, max_lines: usize) -> String {
+		self.lines.iter()
+			.take(max_lines)
+			.map(|line| line.text.trim())
+			.filter(|text| !text.is_empty())
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+
+	pub(super) fn line(&self, index: usize) -> &str {
+		&self.lines[index].text
+	}
+
+	/// The text of the lines in `range`, without the trailing line break.
+	pub(super) fn lines_text(&self, range: std::ops::Range<usize>) -> String {
+		self.lines[range].iter()
+			.map(|line| line.text.as_str())
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+
+	/// Lines that hold a heading, with the level of the heading.
+	fn heading_lines(&self) -> Vec<(usize, usize)> {
+		self.lines.iter().enumerate()
+			.filter_map(|(i, line)| {
+				let heading = line.heading()?;
+				Some((i, heading.chars().take_while(|c| *c == '#').count()))
+			})
+			.collect()
+	}
+
+	/// Line ranges of the sections, in the same order as `sections`.
+	pub(super) fn section_ranges(&self) -> Vec<std::ops::Range<usize>> {
+		let starts = self.heading_lines();
+		starts.iter().enumerate()
+			.map(|(i, (start, _))| *start..starts.get(i + 1).map(|(line, _)| *line).unwrap_or(self.lines.len()))
+			.collect()
+	}
+
+	/// Line range of the section at `index` together with its subsections,
+	/// that is the following sections with deeper headings.
+	pub(super) fn section_tree_range(&self, index: usize) -> Option<std::ops::Range<usize>> {
+		let starts = self.heading_lines();
+		let (start, level) = *starts.get(index)?;
+		let end = starts[index + 1..].iter()
+			.find(|(_, other)| *other <= level)
+			.map(|(line, _)| *line)
+			.unwrap_or(self.lines.len());
+		Some(start..end)
+	}
+
+	/// Headings with the word count of their section, which spans until the
+	/// next heading. Lines before the first heading do not belong to any section.
+	pub(super) fn sections(&self) -> Vec<(String, usize)> {
+		let mut sections: Vec<(String, usize)> = Vec::new();
+		for line in self.lines.iter() {
+			match (line.heading(), sections.last_mut()) {
+				(Some(heading), _) => sections.push((heading.to_owned(), line.counted(&self.rules))),
+				(None, Some((_, word_count))) => *word_count += line.counted(&self.rules),
+				(None, None) => (),
+			}
+		}
+		sections
+	}
+}
@@ -0,0 +1,165 @@
+use relm4::{
+	gtk, gtk::prelude::*,
+};
+use sourceview5::prelude::*;
+
+const DIM: &str = "focus-dim";
+/// Style that the style schemes of the app have for dimmed text.
+const DIM_STYLE: &str = "austeur:focus-dim";
+/// Marks around the text left lit by the last call to `focus`.
+const LIT_START: &str = "focus-lit-start";
+const LIT_END: &str = "focus-lit-end";
+
+/// How much of the text around the cursor stays lit in focus mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum FocusScope {
+	Sentence,
+	Paragraph,
+}
+
+impl FocusScope {
+	pub(super) fn id(&self) -> &'static str {
+		match self {
+			FocusScope::Sentence => "sentence",
+			FocusScope::Paragraph => "paragraph",
+		}
+	}
+
+	pub(super) fn from_id(id: &str) -> Option<Self> {
+		[FocusScope::Sentence, FocusScope::Paragraph].into_iter().find(|scope| scope.id() == id)
+	}
+}
+
+fn is_blank(buffer: &gtk::TextBuffer, line: i32) -> bool {
+	let Some(start) = buffer.iter_at_line(line) else {
+		return true;
+	};
+	let mut end = start.clone();
+	if !end.ends_line() {
+		end.forward_to_line_end();
+	}
+	buffer.text(&start, &end, false).trim().is_empty()
+}
+
+/// Bounds of the paragraph around `iter`, which blank lines delimit.
+fn paragraph_bounds(buffer: &gtk::TextBuffer, iter: &gtk::TextIter) -> (gtk::TextIter, gtk::TextIter) {
+	let mut first = iter.line();
+	while first > 0 && !is_blank(buffer, first - 1) {
+		first -= 1;
+	}
+	let mut last = iter.line();
+	while last + 1 < buffer.line_count() && !is_blank(buffer, last + 1) {
+		last += 1;
+	}
+	let start = buffer.iter_at_line(first).unwrap_or_else(|| iter.clone());
+	let mut end = buffer.iter_at_line(last).unwrap_or_else(|| iter.clone());
+	if !end.ends_line() {
+		end.forward_to_line_end();
+	}
+	(start, end)
+}
+
+/// Bounds of the sentence around `iter`, kept within its paragraph.
+fn sentence_bounds(buffer: &gtk::TextBuffer, iter: &gtk::TextIter) -> (gtk::TextIter, gtk::TextIter) {
+	let (paragraph_start, paragraph_end) = paragraph_bounds(buffer, iter);
+	let mut start = iter.clone();
+	if !start.starts_sentence() {
+		start.backward_sentence_start();
+	}
+	let mut end = iter.clone();
+	if !end.ends_sentence() {
+		end.forward_sentence_end();
+	}
+	if start < paragraph_start {
+		start = paragraph_start;
+	}
+	if end > paragraph_end {
+		end = paragraph_end;
+	}
+	(start, end)
+}
+
+fn dim_tag(buffer: &sourceview5::Buffer) -> gtk::TextTag {
+	let table = buffer.tag_table();
+	let tag = table.lookup(DIM).unwrap_or_else(|| {
+		let tag = gtk::TextTag::new(Some(DIM));
+		table.add(&tag);
+		refresh_color(buffer);
+		tag
+	});
+	// Above any tag the style scheme brings in.
+	tag.set_priority(table.size() - 1);
+	tag
+}
+
+/// Gives the dimmed text the color the style scheme has for it, or the one
+/// of comments in schemes made without focus mode in mind.
+pub(super) fn refresh_color(buffer: &sourceview5::Buffer) {
+	let Some(tag) = buffer.tag_table().lookup(DIM) else {
+		return;
+	};
+	let foreground = buffer.style_scheme()
+		.and_then(|scheme| scheme.style(DIM_STYLE).or_else(|| scheme.style("def:comment")))
+		.filter(|style| style.is_foreground_set())
+		.and_then(|style| style.foreground());
+	tag.set_foreground(foreground.as_deref());
+}
+
+/// Dims all of the text but the sentence or paragraph of the cursor. Only
+/// the text that was lit before, or is lit now, is tagged again.
+pub(super) fn focus(buffer: &sourceview5::Buffer, scope: FocusScope) {
+	let tag = dim_tag(buffer);
+	let cursor = buffer.iter_at_mark(&buffer.get_insert());
+	let (start, end) = match scope {
+		FocusScope::Sentence => sentence_bounds(buffer.upcast_ref(), &cursor),
+		FocusScope::Paragraph => paragraph_bounds(buffer.upcast_ref(), &cursor),
+	};
+	match (buffer.mark(LIT_START), buffer.mark(LIT_END)) {
+		(Some(lit_start), Some(lit_end)) => {
+			let (previous_start, previous_end) = (buffer.iter_at_mark(&lit_start), buffer.iter_at_mark(&lit_end));
+			if previous_start < start {
+				buffer.apply_tag(&tag, &previous_start, &std::cmp::min(previous_end.clone(), start.clone()));
+			}
+			if previous_end > end {
+				buffer.apply_tag(&tag, &std::cmp::max(previous_start, end.clone()), &previous_end);
+			}
+			buffer.move_mark(&lit_start, &start);
+			buffer.move_mark(&lit_end, &end);
+		},
+		_ => {
+			let (buffer_start, buffer_end) = buffer.bounds();
+			buffer.apply_tag(&tag, &buffer_start, &buffer_end);
+			buffer.create_mark(Some(LIT_START), &start, true);
+			buffer.create_mark(Some(LIT_END), &end, false);
+		},
+	}
+	buffer.remove_tag(&tag, &start, &end);
+}
+
+/// Dims the `lines`, whose text was just replaced and so came in untagged.
+/// The lit text is then lit again by `focus`.
+pub(super) fn dim_lines(buffer: &sourceview5::Buffer, lines: std::ops::Range<i32>) {
+	if lines.is_empty() {
+		return;
+	}
+	let (Some(start), Some(mut end)) = (buffer.iter_at_line(lines.start), buffer.iter_at_line(lines.end - 1)) else {
+		return;
+	};
+	if !end.ends_line() {
+		end.forward_to_line_end();
+	}
+	buffer.apply_tag(&dim_tag(buffer), &start, &end);
+}
+
+/// Lights up all of the text again.
+pub(super) fn clear(buffer: &sourceview5::Buffer) {
+	if let Some(tag) = buffer.tag_table().lookup(DIM) {
+		let (start, end) = buffer.bounds();
+		buffer.remove_tag(&tag, &start, &end);
+	}
+	for name in [LIT_START, LIT_END] {
+		if let Some(mark) = buffer.mark(name) {
+			buffer.delete_mark(&mark);
+		}
+	}
+}
@@ -0,0 +1,587 @@
+use relm4::{
+	gtk, gtk::prelude::*,
+};
+
+use crate::i18n::{
+	i18n, i18n_f,
+};
+
+/// Markdown the writer can apply to the selection, from the Formatting
+/// panel or with a shortcut.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Format {
+	Bold,
+	Italic,
+	Strikethrough,
+	Link,
+	/// A heading of level 1 to 6.
+	Heading(u8),
+	Quote,
+	BulletList,
+	NumberedList,
+	HorizontalRule,
+	SceneBreak,
+}
+
+lazy_static::lazy_static! {
+	static ref HEADING: regex::Regex = regex::Regex::new(r"^(#{1,6})[ \t]+").unwrap();
+	static ref QUOTE: regex::Regex = regex::Regex::new(r"^>[ \t]?").unwrap();
+	static ref BULLET: regex::Regex = regex::Regex::new(r"^([ \t]*)[-*+][ \t]+").unwrap();
+	static ref NUMBER: regex::Regex = regex::Regex::new(r"^([ \t]*)\d+[.)][ \t]+").unwrap();
+	static ref LINK: regex::Regex = regex::Regex::new(r"\[([^\]]*)\]\(([^)]*)\)").unwrap();
+}
+
+impl Format {
+	pub(super) const ALL: [Format; 15] = [
+		Format::Bold,
+		Format::Italic,
+		Format::Strikethrough,
+		Format::Link,
+		Format::Heading(1),
+		Format::Heading(2),
+		Format::Heading(3),
+		Format::Heading(4),
+		Format::Heading(5),
+		Format::Heading(6),
+		Format::Quote,
+		Format::BulletList,
+		Format::NumberedList,
+		Format::HorizontalRule,
+		Format::SceneBreak,
+	];
+
+	/// Name of the format as the target of the `app.format` action.
+	pub(super) fn id(&self) -> String {
+		match self {
+			Format::Bold => "bold".into(),
+			Format::Italic => "italic".into(),
+			Format::Strikethrough => "strikethrough".into(),
+			Format::Link => "link".into(),
+			Format::Heading(level) => format!("heading{}", level),
+			Format::Quote => "quote".into(),
+			Format::BulletList => "bullet-list".into(),
+			Format::NumberedList => "numbered-list".into(),
+			Format::HorizontalRule => "horizontal-rule".into(),
+			Format::SceneBreak => "scene-break".into(),
+		}
+	}
+
+	pub(super) fn from_id(id: &str) -> Option<Self> {
+		Format::ALL.into_iter().find(|format| format.id() == id)
+	}
+
+	pub(super) fn label(&self) -> String {
+		match self {
+			Format::Bold => i18n("Bold"),
+			Format::Italic => i18n("Italic"),
+			Format::Strikethrough => i18n("Strikethrough"),
+			Format::Link => i18n("Link"),
+			Format::Heading(level) => i18n_f("Heading {level}", &[("level", &level.to_string())]),
+			Format::Quote => i18n("Block Quote"),
+			Format::BulletList => i18n("Bulleted List"),
+			Format::NumberedList => i18n("Numbered List"),
+			Format::HorizontalRule => i18n("Horizontal Rule"),
+			Format::SceneBreak => i18n("Scene Break"),
+		}
+	}
+
+	pub(super) fn accelerator(&self) -> String {
+		match self {
+			Format::Bold => "<Control>b".into(),
+			Format::Italic => "<Control>i".into(),
+			Format::Strikethrough => "<Control><Shift>x".into(),
+			Format::Link => "<Control>k".into(),
+			Format::Heading(level) => format!("<Control>{}", level),
+			Format::Quote => "<Control>apostrophe".into(),
+			Format::BulletList => "<Control><Shift>l".into(),
+			Format::NumberedList => "<Control><Shift>o".into(),
+			Format::HorizontalRule => "<Control><Shift>h".into(),
+			Format::SceneBreak => "<Control><Shift>b".into(),
+		}
+	}
+
+	/// Applies the format to the selection, or removes it when the selection
+	/// already has it, as a single step to undo.
+	pub(super) fn toggle(&self, buffer: &gtk::TextBuffer) {
+		let (start, end) = buffer.selection_bounds().unwrap_or_else(|| {
+			let cursor = buffer.iter_at_mark(&buffer.get_insert());
+			(cursor.clone(), cursor)
+		});
+		let text = Text {
+			chars: buffer.text(&buffer.start_iter(), &buffer.end_iter(), false).chars().collect(),
+			start: start.offset() as usize,
+			end: end.offset() as usize,
+			cursor: buffer.iter_at_mark(&buffer.get_insert()).offset() as usize,
+		};
+		buffer.begin_user_action();
+		self.change(&text).apply(buffer);
+		buffer.end_user_action();
+	}
+
+	fn change(&self, text: &Text) -> Change {
+		match self {
+			Format::Bold => toggle_inline(text, "**"),
+			Format::Italic => toggle_inline(text, "*"),
+			Format::Strikethrough => toggle_inline(text, "~~"),
+			Format::Link => toggle_link(text),
+			Format::Heading(level) => toggle_heading(text, *level as usize),
+			Format::Quote => toggle_quote(text),
+			Format::BulletList => toggle_list(text, false),
+			Format::NumberedList => toggle_list(text, true),
+			Format::HorizontalRule => toggle_block(text, "---"),
+			Format::SceneBreak => toggle_block(text, "* * *"),
+		}
+	}
+}
+
+/// The text of the buffer along with its selection, in characters.
+#[derive(Debug, Clone)]
+struct Text {
+	chars: Vec<char>,
+	start: usize,
+	end: usize,
+	/// Where the cursor is, at one end of the selection.
+	cursor: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Edit {
+	Delete(usize, usize),
+	Insert(usize, String),
+}
+
+/// What a format does to the text, in characters.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Change {
+	/// Each made to the text the previous ones left.
+	edits: Vec<Edit>,
+	/// The selection to make afterwards, if it isn't to be left where it is.
+	selection: Option<(usize, usize)>,
+}
+
+impl Change {
+	fn delete(&mut self, start: usize, end: usize) {
+		if start < end {
+			self.edits.push(Edit::Delete(start, end));
+		}
+	}
+
+	fn insert(&mut self, offset: usize, text: &str) {
+		if !text.is_empty() {
+			self.edits.push(Edit::Insert(offset, text.to_string()));
+		}
+	}
+
+	fn select(&mut self, start: usize, end: usize) {
+		self.selection = Some((start, end));
+	}
+
+	/// Replaces the first `removed` characters of the line starting at
+	/// `offset` with `prefix`. Lines are changed from the last up, so that
+	/// where those before start still holds.
+	fn set_line_prefix(&mut self, offset: usize, removed: usize, prefix: &str) {
+		self.delete(offset, offset + removed);
+		self.insert(offset, prefix);
+	}
+
+	fn apply(&self, buffer: &gtk::TextBuffer) {
+		for edit in self.edits.iter() {
+			match edit {
+				Edit::Delete(start, end) => buffer.delete(&mut buffer.iter_at_offset(*start as i32), &mut buffer.iter_at_offset(*end as i32)),
+				Edit::Insert(offset, text) => buffer.insert(&mut buffer.iter_at_offset(*offset as i32), text),
+			}
+		}
+		if let Some((start, end)) = self.selection {
+			buffer.select_range(&buffer.iter_at_offset(start as i32), &buffer.iter_at_offset(end as i32));
+		}
+	}
+}
+
+impl Text {
+	/// Whether the character at `index` is part of a word. Apostrophes are,
+	/// within one.
+	fn is_word_char(&self, index: usize) -> bool {
+		match self.chars.get(index) {
+			Some(c) if c.is_alphanumeric() => true,
+			Some('\'' | '’') => {
+				index > 0
+					&& self.chars[index - 1].is_alphanumeric()
+					&& self.chars.get(index + 1).is_some_and(|c| c.is_alphanumeric())
+			},
+			_ => false,
+		}
+	}
+
+	/// The selection, or the word under the cursor when nothing is selected.
+	fn selection_or_word(&self) -> (usize, usize) {
+		if self.start != self.end {
+			return (self.start, self.end);
+		}
+		let (mut start, mut end) = (self.start, self.end);
+		while start > 0 && self.is_word_char(start - 1) {
+			start -= 1;
+		}
+		while self.is_word_char(end) {
+			end += 1;
+		}
+		(start, end)
+	}
+
+	/// How many times `c` repeats before, or after, `offset`.
+	fn run_length(&self, offset: usize, c: char, forward: bool) -> usize {
+		if forward {
+			self.chars[offset..].iter().take_while(|other| **other == c).count()
+		} else {
+			self.chars[..offset].iter().rev().take_while(|other| **other == c).count()
+		}
+	}
+
+	fn slice(&self, start: usize, end: usize) -> String {
+		self.chars[start..end].iter().collect()
+	}
+
+	fn line_start(&self, offset: usize) -> usize {
+		self.chars[..offset].iter().rposition(|c| *c == '\n').map_or(0, |index| index + 1)
+	}
+
+	fn line_end(&self, offset: usize) -> usize {
+		self.chars[offset..].iter().position(|c| *c == '\n').map_or(self.chars.len(), |index| offset + index)
+	}
+
+	/// Lines touched by the selection, as where each starts along with its
+	/// text. A selection ending at the very start of a line leaves that line
+	/// out.
+	fn selected_lines(&self) -> Vec<(usize, String)> {
+		let first = self.line_start(self.start);
+		let last = match self.line_start(self.end) {
+			last if last == self.end && last > first => self.line_start(last - 1),
+			last => last,
+		};
+		let mut lines = vec![];
+		let mut start = first;
+		loop {
+			let end = self.line_end(start);
+			lines.push((start, self.slice(start, end)));
+			if start >= last {
+				return lines;
+			}
+			start = end + 1;
+		}
+	}
+}
+
+/// Length, in characters, of what `pattern` matches at the start of `text`.
+fn prefix_length(pattern: &regex::Regex, text: &str) -> Option<usize> {
+	pattern.find(text).map(|found| found.as_str().chars().count())
+}
+
+/// Wraps the selection in `marker`, or unwraps it. Asterisks are shared by
+/// bold and italic, so `***` counts as both.
+fn toggle_inline(text: &Text, marker: &str) -> Change {
+	let (start, end) = text.selection_or_word();
+	let k = marker.chars().count();
+	let c = marker.chars().next().unwrap_or('*');
+	let selected = text.slice(start, end);
+	let mut change = Change::default();
+
+	let around = text.run_length(start, c, false).min(text.run_length(end, c, true));
+	let is_wrapped = if c == '*' {
+		around == k || around == 3
+	} else {
+		around >= k
+	};
+	if is_wrapped {
+		change.delete(end, end + k);
+		change.delete(start - k, start);
+		change.select(start - k, end - k);
+	} else if selected.chars().count() >= 2 * k && selected.starts_with(marker) && selected.ends_with(marker) {
+		change.delete(end - k, end);
+		change.delete(start, start + k);
+		change.select(start, end - 2 * k);
+	} else {
+		change.insert(end, marker);
+		change.insert(start, marker);
+		change.select(start + k, end + k);
+	}
+	change
+}
+
+/// Turns the selection into the text of a link, with the target selected to
+/// be typed over, or turns the link under the cursor back into its text.
+fn toggle_link(text: &Text) -> Change {
+	let (start, end) = text.selection_or_word();
+	let line_start = text.line_start(start);
+	let line = text.slice(line_start, text.line_end(start));
+	let (from, to) = (start - line_start, end - line_start);
+	let link = LINK.captures_iter(&line).find_map(|captures| {
+		let whole = captures.get(0)?;
+		let link_text = captures.get(1)?;
+		let link_start = line[..whole.start()].chars().count();
+		let link_end = link_start + whole.as_str().chars().count();
+		(link_start <= from && to <= link_end).then(|| (link_start, link_end, link_text.as_str().to_string()))
+	});
+	let mut change = Change::default();
+
+	if let Some((link_start, link_end, link_text)) = link {
+		let link_start = line_start + link_start;
+		change.delete(link_start, line_start + link_end);
+		change.insert(link_start, &link_text);
+		change.select(link_start, link_start + link_text.chars().count());
+		return change;
+	}
+
+	let selected = text.slice(start, end);
+	let target = i18n("url");
+	change.delete(start, end);
+	change.insert(start, &format!("[{}]({})", selected, target));
+	if selected.is_empty() {
+		change.select(start + 1, start + 1);
+	} else {
+		let target_start = start + selected.chars().count() + 3;
+		change.select(target_start, target_start + target.chars().count());
+	}
+	change
+}
+
+fn toggle_heading(text: &Text, level: usize) -> Change {
+	let lines: Vec<(usize, String)> = text.selected_lines().into_iter()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.collect();
+	let is_on = !lines.is_empty() && lines.iter().all(|(_, line)| {
+		HEADING.captures(line).is_some_and(|captures| captures[1].len() == level)
+	});
+	let prefix = if is_on { String::new() } else { format!("{} ", "#".repeat(level)) };
+	let mut change = Change::default();
+	for (start, line) in lines.into_iter().rev() {
+		change.set_line_prefix(start, prefix_length(&HEADING, &line).unwrap_or(0), &prefix);
+	}
+	change
+}
+
+fn toggle_quote(text: &Text) -> Change {
+	let lines = text.selected_lines();
+	let is_on = lines.iter().all(|(_, line)| QUOTE.is_match(line));
+	let mut change = Change::default();
+	for (start, line) in lines.into_iter().rev() {
+		if is_on {
+			change.set_line_prefix(start, prefix_length(&QUOTE, &line).unwrap_or(0), "");
+		} else {
+			change.set_line_prefix(start, 0, "> ");
+		}
+	}
+	change
+}
+
+/// Makes a list of the selected lines, replacing the markers of any other
+/// kind of list, and numbering in order.
+fn toggle_list(text: &Text, is_numbered: bool) -> Change {
+	let pattern: &regex::Regex = if is_numbered { &NUMBER } else { &BULLET };
+	let lines: Vec<(usize, String)> = text.selected_lines().into_iter()
+		.filter(|(_, line)| !line.trim().is_empty())
+		.collect();
+	let is_on = !lines.is_empty() && lines.iter().all(|(_, line)| pattern.is_match(line));
+	let mut change = Change::default();
+	for (index, (start, line)) in lines.into_iter().enumerate().rev() {
+		let (removed, indent) = match BULLET.captures(&line).or_else(|| NUMBER.captures(&line)) {
+			Some(captures) => (captures[0].chars().count(), captures[1].to_string()),
+			None => (0, String::new()),
+		};
+		let prefix = if is_on {
+			indent
+		} else if is_numbered {
+			format!("{}{}. ", indent, index + 1)
+		} else {
+			format!("{}- ", indent)
+		};
+		change.set_line_prefix(start, removed, &prefix);
+	}
+	change
+}
+
+/// Puts `block` on a line of its own after the cursor, or removes it when the
+/// cursor is on it.
+fn toggle_block(text: &Text, block: &str) -> Change {
+	let start = text.line_start(text.cursor);
+	let end = text.line_end(text.cursor);
+	let line = text.slice(start, end);
+	let mut change = Change::default();
+
+	if line.trim() == block {
+		// Along with the line break that follows, if there is one.
+		change.delete(start, (end + 1).min(text.chars.len()));
+		change.select(start, start);
+	} else if line.trim().is_empty() {
+		change.delete(start, end);
+		change.insert(start, &format!("{}\n", block));
+		let after = start + block.chars().count() + 1;
+		change.select(after, after);
+	} else {
+		change.insert(end, &format!("\n\n{}\n", block));
+		let after = end + block.chars().count() + 3;
+		change.select(after, after);
+	}
+	change
+}
+
+/// The Formatting panel: a row per format, grouped, with its shortcut.
+pub(super) fn formatting_panel(on_apply: impl Fn(Format) + 'static) -> gtk::ScrolledWindow {
+	let on_apply = std::rc::Rc::new(on_apply);
+	relm4::view! {
+		panel = gtk::ScrolledWindow {
+			set_hscrollbar_policy: gtk::PolicyType::Never,
+			set_vexpand: true,
+
+			#[wrap(Some)]
+			set_child: content = &gtk::Box {
+				set_orientation: gtk::Orientation::Vertical,
+				set_spacing: 12,
+				set_margin_top: 6,
+				set_margin_bottom: 6,
+				set_margin_start: 6,
+				set_margin_end: 6,
+			},
+		}
+	}
+
+	let all = Format::ALL;
+	let groups = [
+		(i18n("Text"), &all[0..4]),
+		(i18n("Headings"), &all[4..10]),
+		(i18n("Blocks"), &all[10..]),
+	];
+	for (title, formats) in groups {
+		relm4::view! {
+			heading = gtk::Label {
+				set_label: &title,
+				set_xalign: 0.0,
+				set_margin_start: 6,
+				add_css_class: "heading",
+			}
+		}
+		relm4::view! {
+			list = gtk::ListBox {
+				add_css_class: "navigation-sidebar",
+				set_selection_mode: gtk::SelectionMode::None,
+
+				connect_row_activated[on_apply, formats = formats.to_vec()] => move |_, row| {
+					if let Some(format) = formats.get(row.index() as usize) {
+						on_apply(*format);
+					}
+				},
+			}
+		}
+		for format in formats {
+			relm4::view! {
+				row = gtk::Box {
+					set_spacing: 12,
+
+					gtk::Label {
+						set_label: &format.label(),
+						set_xalign: 0.0,
+						set_hexpand: true,
+					},
+
+					gtk::ShortcutLabel::new(&format.accelerator()) {},
+				}
+			}
+			list.append(&row);
+		}
+		content.append(&heading);
+		content.append(&list);
+	}
+
+	panel
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Edit, Format, Text};
+
+	/// Applies `format` to `text`, where `⟨` and `⟩` mark the selection and
+	/// `|` the cursor, marking the selection made afterwards the same way.
+	fn toggle(format: Format, text: &str) -> String {
+		let mut chars: Vec<char> = vec![];
+		let (mut start, mut end) = (0, 0);
+		for c in text.chars() {
+			match c {
+				'⟨' => start = chars.len(),
+				'⟩' => end = chars.len(),
+				'|' => (start, end) = (chars.len(), chars.len()),
+				c => chars.push(c),
+			}
+		}
+		let change = format.change(&Text { chars: chars.clone(), start, end, cursor: end });
+		for edit in change.edits {
+			match edit {
+				Edit::Delete(start, end) => {
+					chars.drain(start..end);
+				},
+				Edit::Insert(offset, text) => {
+					chars.splice(offset..offset, text.chars());
+				},
+			}
+		}
+		match change.selection {
+			Some((start, end)) if start == end => chars.insert(start, '|'),
+			Some((start, end)) => {
+				chars.insert(end, '⟩');
+				chars.insert(start, '⟨');
+			},
+			None => (),
+		}
+		chars.into_iter().collect()
+	}
+
+	#[test]
+	fn toggles_inline_formats() {
+		for (format, text, formatted) in [
+			(Format::Bold, "a ⟨word⟩ here", "a **⟨word⟩** here"),
+			(Format::Italic, "a ⟨word⟩ here", "a *⟨word⟩* here"),
+			(Format::Strikethrough, "a ⟨word⟩ here", "a ~~⟨word⟩~~ here"),
+			(Format::Italic, "**⟨word⟩**", "***⟨word⟩***"),
+		] {
+			assert_eq!(toggle(format, text), formatted);
+			assert_eq!(toggle(format, formatted), text);
+		}
+		assert_eq!(toggle(Format::Bold, "it's a wo|rd"), "it's a **⟨word⟩**");
+		assert_eq!(toggle(Format::Bold, "it'|s"), "**⟨it's⟩**");
+		assert_eq!(toggle(Format::Bold, "a ⟨**word**⟩"), "a ⟨word⟩");
+	}
+
+	#[test]
+	fn toggles_links() {
+		assert_eq!(toggle(Format::Link, "see ⟨the docs⟩"), "see [the docs](⟨url⟩)");
+		assert_eq!(toggle(Format::Link, "see [the do|cs](url)"), "see ⟨the docs⟩");
+		assert_eq!(toggle(Format::Link, "a | b"), "a [|](url) b");
+	}
+
+	#[test]
+	fn toggles_headings() {
+		assert_eq!(toggle(Format::Heading(2), "⟨One\n\nTwo⟩"), "## One\n\n## Two");
+		assert_eq!(toggle(Format::Heading(2), "⟨## One\n\n## Two⟩"), "One\n\nTwo");
+		assert_eq!(toggle(Format::Heading(2), "# O|ne"), "## One");
+		assert_eq!(toggle(Format::Heading(1), "⟨One\n⟩Two"), "# One\nTwo");
+	}
+
+	#[test]
+	fn toggles_quotes() {
+		assert_eq!(toggle(Format::Quote, "⟨a\nb⟩"), "> a\n> b");
+		assert_eq!(toggle(Format::Quote, "⟨> a\n>b⟩"), "a\nb");
+	}
+
+	#[test]
+	fn toggles_lists() {
+		assert_eq!(toggle(Format::NumberedList, "⟨a\n\nb⟩"), "1. a\n\n2. b");
+		assert_eq!(toggle(Format::BulletList, "⟨1. a\n  2. b⟩"), "- a\n  - b");
+		assert_eq!(toggle(Format::BulletList, "⟨- a\n  - b⟩"), "a\n  b");
+		assert_eq!(toggle(Format::NumberedList, "⟨1. a\n2) b⟩"), "a\nb");
+	}
+
+	#[test]
+	fn toggles_blocks() {
+		assert_eq!(toggle(Format::HorizontalRule, "Te|xt"), "Text\n\n---\n|");
+		assert_eq!(toggle(Format::HorizontalRule, "Text\n\n--|-\n"), "Text\n\n|");
+		assert_eq!(toggle(Format::SceneBreak, "a\n|\nb"), "a\n* * *\n|\nb");
+		assert_eq!(toggle(Format::SceneBreak, "a\n* |* *\nb"), "a\n|b");
+	}
+}
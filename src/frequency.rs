@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+/// Number of words and of phrases kept in a report.
+const TOP: usize = 20;
+/// Repeats below this are too few to call a word overused.
+const MIN_OVERUSE_COUNT: usize = 3;
+/// How many times its baseline rate a word must reach to be flagged.
+const OVERUSE_FACTOR: f64 = 2.0;
+/// Baseline of the words missing from the table, per 10,000 words.
+const DEFAULT_BASELINE: f64 = 15.0;
+/// Words that drop their vowel before another, as in "l'homme" or "qu'il".
+const ELISIONS: [&str; 12] = ["c", "d", "j", "l", "m", "n", "s", "t", "qu", "jusqu", "lorsqu", "puisqu"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Language {
+	English,
+	French,
+	Spanish,
+	Vietnamese,
+}
+
+impl Language {
+	const ALL: [Language; 4] = [Language::English, Language::French, Language::Spanish, Language::Vietnamese];
+
+	fn id(&self) -> &'static str {
+		match self {
+			Language::English => "en",
+			Language::French => "fr",
+			Language::Spanish => "es",
+			Language::Vietnamese => "vi",
+		}
+	}
+
+	fn stop_words(&self) -> &'static [&'static str] {
+		match self {
+			Language::English => &[
+				"a", "about", "after", "all", "an", "and", "any", "are", "as", "at", "be", "been", "but", "by",
+				"can", "could", "did", "do", "for", "from", "had", "has", "have", "he", "her", "him", "his",
+				"i", "if", "in", "into", "is", "it", "its", "it's", "me", "my", "no", "not", "of", "on", "or",
+				"our", "out", "she", "so", "than", "that", "the", "their", "them", "then", "there", "they",
+				"this", "to", "up", "us", "was", "we", "were", "what", "when", "which", "who", "will", "with",
+				"would", "you", "your",
+			],
+			Language::French => &[
+				"à", "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en", "et", "il",
+				"ils", "je", "la", "le", "les", "leur", "lui", "ma", "mais", "me", "mon", "ne", "nous", "on",
+				"ou", "par", "pas", "pour", "que", "qui", "sa", "se", "ses", "son", "sur", "ta", "te", "tu",
+				"un", "une", "vous", "y", "est", "était", "c'", "d'", "j'", "l'", "m'", "n'", "qu'", "s'",
+				"t'", "jusqu'", "lorsqu'", "puisqu'",
+			],
+			Language::Spanish => &[
+				"a", "al", "con", "de", "del", "el", "ella", "en", "es", "esta", "este", "la", "las", "le",
+				"lo", "los", "me", "mi", "no", "nos", "para", "pero", "por", "que", "se", "si", "su", "sus",
+				"te", "tu", "un", "una", "y", "yo", "era", "como", "más",
+			],
+			Language::Vietnamese => &[
+				"và", "của", "là", "có", "không", "những", "các", "một", "được", "trong", "cho", "với",
+				"này", "đã", "thì", "mà", "để", "khi", "như", "cũng", "ra", "vào", "lại", "đến", "từ", "nó",
+				"tôi", "ở", "nhưng", "sẽ", "bị", "vì", "nên", "còn", "thế", "gì", "đó", "rồi", "đang", "ta",
+			],
+		}
+	}
+
+	/// Rates, per 10,000 words, of words writers tend to lean on, taken from
+	/// general fiction and non-fiction.
+	fn baselines(&self) -> &'static [(&'static str, f64)] {
+		match self {
+			Language::English => &[
+				("just", 12.0), ("really", 6.0), ("very", 10.0), ("suddenly", 1.5), ("actually", 3.0),
+				("quite", 3.0), ("literally", 0.5), ("basically", 0.5), ("felt", 6.0), ("looked", 8.0),
+				("seemed", 5.0), ("started", 3.0), ("began", 4.0), ("thing", 5.0), ("things", 4.0),
+				("something", 8.0), ("somehow", 1.0), ("almost", 4.0), ("even", 10.0), ("still", 10.0),
+				("only", 12.0), ("like", 15.0), ("well", 8.0), ("turned", 4.0), ("smiled", 2.0),
+				("nodded", 1.5), ("sighed", 1.0), ("shrugged", 1.0), ("eyes", 8.0), ("back", 15.0),
+			],
+			Language::French => &[
+				("vraiment", 3.0), ("très", 10.0), ("soudain", 1.5), ("alors", 8.0), ("juste", 4.0),
+				("chose", 5.0), ("quelque", 6.0), ("encore", 8.0),
+			],
+			Language::Spanish => &[
+				("muy", 10.0), ("realmente", 2.0), ("entonces", 6.0), ("repente", 1.5), ("cosa", 5.0),
+				("algo", 8.0), ("todavía", 4.0),
+			],
+			Language::Vietnamese => &[
+				("rất", 10.0), ("thật", 6.0), ("lắm", 4.0), ("bỗng", 2.0), ("đột", 1.5), ("nhiên", 2.0),
+				("chỉ", 10.0), ("vẫn", 8.0),
+			],
+		}
+	}
+
+	/// Whether a word is too common to tell anything, whichever apostrophe it
+	/// is written with.
+	fn is_stop_word(&self, token: &str) -> bool {
+		self.stop_words().contains(&token.replace('’', "'").as_str())
+	}
+
+	/// The language whose stop words show up the most.
+	fn detect(tokens: &[String]) -> Self {
+		Language::ALL.into_iter()
+			.max_by_key(|language| tokens.iter().filter(|token| language.is_stop_word(token)).count())
+			.unwrap_or(Language::English)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct WordFrequency {
+	pub(super) term: String,
+	pub(super) count: usize,
+	/// Uses per 10,000 words.
+	pub(super) rate: f64,
+	pub(super) is_overused: bool,
+}
+
+/// The most repeated words and phrases of a text.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct FrequencyReport {
+	pub(super) language: Language,
+	pub(super) words: Vec<WordFrequency>,
+	pub(super) phrases: Vec<WordFrequency>,
+}
+
+/// Breaks between sentences, after which words are capitalized anyway.
+fn is_sentence_break(c: char) -> bool {
+	matches!(c, '.' | '!' | '?' | '…' | '\n' | ';' | ':')
+}
+
+/// Breaks within sentences as well, which phrases don't run across.
+fn is_clause_break(c: char) -> bool {
+	is_sentence_break(c) || matches!(c, ',' | '—' | '–')
+}
+
+/// Splits an elided word, apostrophe included, from the word after it.
+fn split_elision(token: &str) -> Vec<&str> {
+	if let Some((index, apostrophe)) = token.char_indices().find(|(_, c)| *c == '\'' || *c == '’') {
+		if ELISIONS.contains(&token[..index].to_lowercase().as_str()) {
+			let end = index + apostrophe.len_utf8();
+			return vec![&token[..end], &token[end..]];
+		}
+	}
+	vec![token]
+}
+
+/// Words of a sentence, in lowercase, along with whether they were capitalized.
+fn tokenize(sentence: &str) -> Vec<(String, bool)> {
+	sentence
+		.split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+		.map(|token| token.trim_matches(|c| c == '\'' || c == '’'))
+		.flat_map(split_elision)
+		.filter(|token| token.chars().any(char::is_alphabetic))
+		.map(|token| (token.to_lowercase(), token.starts_with(char::is_uppercase)))
+		.collect()
+}
+
+/// Words and phrases repeated in the text, the most repeated first.
+fn rank(counts: HashMap<String, usize>, total: usize) -> Vec<WordFrequency> {
+	let mut ranked: Vec<WordFrequency> = counts.into_iter()
+		.filter(|(_, count)| *count > 1)
+		.map(|(term, count)| WordFrequency {
+			term,
+			count,
+			rate: count as f64 * 10_000.0 / total.max(1) as f64,
+			is_overused: false,
+		})
+		.collect();
+	ranked.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+	ranked
+}
+
+/// Joins the words of a phrase as they are written, with elided words
+/// leaning on the next.
+fn join_phrase(words: &[String]) -> String {
+	let mut phrase = String::new();
+	for word in words {
+		if !(phrase.is_empty() || phrase.ends_with(['\'', '’'])) {
+			phrase.push(' ');
+		}
+		phrase.push_str(word);
+	}
+	phrase
+}
+
+impl FrequencyReport {
+	pub(super) fn compute(text: &str) -> Self {
+		let tokenized: Vec<Vec<(String, bool)>> = text
+			.split(is_sentence_break)
+			.map(tokenize)
+			.filter(|tokens| !tokens.is_empty())
+			.collect();
+		// Words capitalized even within sentences are names, which are
+		// expected to come back often.
+		let mut capitalized: HashMap<&str, (usize, usize)> = HashMap::new();
+		for (token, is_capitalized) in tokenized.iter().flat_map(|tokens| tokens.iter().skip(1)) {
+			let (upper, total) = capitalized.entry(token.as_str()).or_default();
+			*upper += *is_capitalized as usize;
+			*total += 1;
+		}
+		let is_name = |token: &str| capitalized.get(token).is_some_and(|(upper, total)| upper * 2 > *total);
+		let all: Vec<String> = tokenized.iter()
+			.flatten()
+			.map(|(token, _)| token.clone())
+			.collect();
+		let language = Language::detect(&all);
+		let is_stop_word = |token: &String| language.is_stop_word(token);
+
+		let mut words: HashMap<String, usize> = HashMap::new();
+		for token in all.iter().filter(|token| !is_stop_word(token)) {
+			*words.entry(token.clone()).or_default() += 1;
+		}
+
+		// Phrases that start or end with a stop word are rarely telling.
+		let mut phrases: HashMap<String, usize> = HashMap::new();
+		let clauses = text.split(is_clause_break)
+			.map(|clause| tokenize(clause).into_iter().map(|(token, _)| token).collect::<Vec<_>>());
+		for clause in clauses {
+			for size in 2..=3 {
+				for gram in clause.windows(size) {
+					if is_stop_word(&gram[0]) || is_stop_word(&gram[size - 1]) {
+						continue;
+					}
+					*phrases.entry(join_phrase(gram)).or_default() += 1;
+				}
+			}
+		}
+
+		let mut words = rank(words, all.len());
+		let baselines = language.baselines();
+		for word in words.iter_mut() {
+			let baseline = baselines.iter()
+				.find(|(term, _)| *term == word.term)
+				.map(|(_, rate)| *rate)
+				.unwrap_or(DEFAULT_BASELINE);
+			word.is_overused = word.count >= MIN_OVERUSE_COUNT
+				&& word.rate > baseline * OVERUSE_FACTOR
+				&& !is_name(&word.term);
+		}
+		// Overused words are kept even when others are repeated more.
+		let mut index = 0;
+		words.retain(|word| {
+			index += 1;
+			index <= TOP || word.is_overused
+		});
+		let mut phrases = rank(phrases, all.len());
+		phrases.truncate(TOP);
+
+		Self {
+			language,
+			words,
+			phrases,
+		}
+	}
+
+	/// The report as JSON, for the chart of the Analysis page.
+	pub(super) fn to_json(&self) -> String {
+		let entries = |entries: &[WordFrequency]| -> Vec<json::JsonValue> {
+			entries.iter()
+				.map(|entry| json::object! {
+					term: entry.term.as_str(),
+					count: entry.count,
+					rate: entry.rate,
+					overused: entry.is_overused,
+				})
+				.collect()
+		};
+		json::stringify(json::object! {
+			language: self.language.id(),
+			words: entries(&self.words),
+			phrases: entries(&self.phrases),
+		})
+	}
+}
@@ -0,0 +1,220 @@
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+use relm4::{
+	adw, adw::prelude::*,
+	gtk, gtk::prelude::*, gtk::glib,
+};
+
+use crate::{
+	history::History,
+	i18n::{
+		i18n, i18n_f, ni18n_f,
+	},
+	stats::metric_row,
+	store::Goal,
+};
+
+/// Number of days the recent pace is averaged over.
+const PACE_DAYS: usize = 14;
+
+fn parse_day(day: &str) -> Option<glib::DateTime> {
+	let mut parts = day.splitn(3, '-').map(|part| part.parse::<i32>().ok());
+	let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+	glib::DateTime::from_local(year, month, day, 0, 0, 0.0).ok()
+}
+
+fn start_of_today() -> Option<glib::DateTime> {
+	let now = glib::DateTime::now_local().ok()?;
+	glib::DateTime::from_local(now.year(), now.month(), now.day_of_month(), 0, 0, 0.0).ok()
+}
+
+fn format_day(day: &glib::DateTime) -> String {
+	day.format("%x").map(|day| day.to_string()).unwrap_or_default()
+}
+
+/// Where a writing stands against its goal.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct GoalProgress {
+	pub(super) goal: Goal,
+	pub(super) words: usize,
+	pub(super) written_today: usize,
+	/// Net words per day, averaged over the last few days.
+	pub(super) recent_pace: f64,
+}
+
+impl GoalProgress {
+	pub(super) fn new(goal: Goal, words: usize, written_today: usize, history: &History, id: &str) -> Self {
+		Self {
+			goal,
+			words,
+			written_today,
+			recent_pace: history.average_net(id, PACE_DAYS),
+		}
+	}
+
+	fn remaining(&self) -> Option<usize> {
+		self.goal.target.map(|target| target.saturating_sub(self.words))
+	}
+
+	/// Whole days left before the deadline, counting today.
+	fn days_left(&self) -> Option<i64> {
+		let deadline = parse_day(self.goal.deadline.as_deref()?)?;
+		let today = start_of_today()?;
+		Some(deadline.difference(&today).as_days() + 1)
+	}
+
+	/// Progress towards the total target, or else towards today's target.
+	pub(super) fn fraction(&self) -> Option<f64> {
+		match (self.goal.target, self.goal.daily_target) {
+			(Some(target), _) => Some(self.words as f64 / target.max(1) as f64),
+			(None, Some(daily_target)) => Some(self.written_today as f64 / daily_target.max(1) as f64),
+			(None, None) => None,
+		}
+	}
+
+	/// Words a day needed to reach the target by the deadline.
+	pub(super) fn required_pace(&self) -> Option<usize> {
+		let remaining = self.remaining()?;
+		let days_left = self.days_left()?;
+		if days_left <= 0 {
+			return None;
+		}
+		Some(remaining.div_ceil(days_left as usize))
+	}
+
+	/// The day the target will be reached at the recent pace.
+	pub(super) fn projected_finish(&self) -> Option<glib::DateTime> {
+		let remaining = self.remaining()?;
+		if self.recent_pace <= 0.0 {
+			return None;
+		}
+		let days = (remaining as f64 / self.recent_pace).ceil() as i32;
+		start_of_today()?.add_days(days).ok()
+	}
+
+	pub(super) fn is_daily_goal_met(&self) -> bool {
+		self.goal.daily_target.is_some_and(|daily_target| self.written_today >= daily_target)
+	}
+
+	/// One line on the progress, for the tooltip of the word count.
+	pub(super) fn summary(&self) -> Option<String> {
+		let mut parts = vec![];
+		if let Some(target) = self.goal.target {
+			parts.push(ni18n_f("{count} / {target} word", "{count} / {target} words", target, &[("count", &self.words.to_string()), ("target", &target.to_string())]));
+		}
+		if let Some(daily_target) = self.goal.daily_target {
+			parts.push(ni18n_f("{count} / {target} word today", "{count} / {target} words today", daily_target, &[("count", &self.written_today.to_string()), ("target", &daily_target.to_string())]));
+		}
+		if let Some(pace) = self.required_pace() {
+			parts.push(ni18n_f("{count} word a day needed", "{count} words a day needed", pace, &[("count", &pace.to_string())]));
+		}
+		if let Some(finish) = self.projected_finish() {
+			parts.push(i18n_f("Finishing around {day}", &[("day", &format_day(&finish))]));
+		}
+		if parts.is_empty() {
+			None
+		} else {
+			Some(parts.join(" · "))
+		}
+	}
+}
+
+fn target_row(title: &str, value: Option<usize>, goal: &Rc<RefCell<Goal>>, on_change: &Rc<dyn Fn(Goal)>, field: fn(&mut Goal) -> &mut Option<usize>) -> adw::SpinRow {
+	let row = adw::SpinRow::with_range(0.0, 1_000_000.0, 100.0);
+	row.set_title(title);
+	row.set_subtitle(&i18n("Words, 0 for none"));
+	row.set_value(value.unwrap_or_default() as f64);
+	let goal = goal.clone();
+	let on_change = on_change.clone();
+	row.connect_value_notify(move |row| {
+		let value = row.value() as usize;
+		*field(&mut goal.borrow_mut()) = Some(value).filter(|value| *value > 0);
+		on_change(goal.borrow().clone());
+	});
+	row
+}
+
+fn deadline_row(goal: &Rc<RefCell<Goal>>, on_change: &Rc<dyn Fn(Goal)>) -> adw::ActionRow {
+	let deadline = goal.borrow().deadline.as_deref().and_then(parse_day);
+	let row = adw::ActionRow::builder()
+		.title(i18n("Deadline"))
+		.subtitle(deadline.as_ref().map(format_day).unwrap_or_else(|| i18n("None")))
+		.build();
+
+	let calendar = gtk::Calendar::new();
+	if let Some(deadline) = &deadline {
+		calendar.select_day(deadline);
+	}
+	let clear_button = gtk::Button::builder()
+		.label(i18n("Clear"))
+		.css_classes(["flat"])
+		.build();
+	let popover_box = gtk::Box::new(gtk::Orientation::Vertical, 6);
+	popover_box.append(&calendar);
+	popover_box.append(&clear_button);
+	let popover = gtk::Popover::builder()
+		.child(&popover_box)
+		.build();
+	let button = gtk::MenuButton::builder()
+		.icon_name("x-office-calendar-symbolic")
+		.tooltip_text(i18n("Choose Deadline"))
+		.valign(gtk::Align::Center)
+		.css_classes(["flat"])
+		.popover(&popover)
+		.build();
+	row.add_suffix(&button);
+
+	{
+		let (goal, on_change, row) = (goal.clone(), on_change.clone(), row.clone());
+		calendar.connect_day_selected(move |calendar| {
+			let day = calendar.date();
+			row.set_subtitle(&format_day(&day));
+			goal.borrow_mut().deadline = day.format("%Y-%m-%d").ok().map(|day| day.to_string());
+			on_change(goal.borrow().clone());
+		});
+	}
+	{
+		let (goal, on_change, row) = (goal.clone(), on_change.clone(), row.clone());
+		clear_button.connect_clicked(move |_| {
+			row.set_subtitle(&i18n("None"));
+			goal.borrow_mut().deadline = None;
+			on_change(goal.borrow().clone());
+			popover.popdown();
+		});
+	}
+	row
+}
+
+/// The page of the Statistics dialog where the goal is set, along with the
+/// pace it asks for.
+pub(super) fn goals_page(progress: &GoalProgress, on_change: impl Fn(Goal) + 'static) -> adw::PreferencesPage {
+	let on_change: Rc<dyn Fn(Goal)> = Rc::new(on_change);
+	let goal = Rc::new(RefCell::new(progress.goal.clone()));
+	let page = adw::PreferencesPage::new();
+
+	let goal_group = adw::PreferencesGroup::builder()
+		.title(i18n("Goal"))
+		.build();
+	goal_group.add(&target_row(&i18n("Word Target"), progress.goal.target, &goal, &on_change, |goal| &mut goal.target));
+	goal_group.add(&deadline_row(&goal, &on_change));
+	goal_group.add(&target_row(&i18n("Daily Target"), progress.goal.daily_target, &goal, &on_change, |goal| &mut goal.daily_target));
+	page.add(&goal_group);
+
+	let pace = adw::PreferencesGroup::builder()
+		.title(i18n("Pace"))
+		.build();
+	pace.add(&metric_row(&i18n("Written Today"), ni18n_f("{count} word", "{count} words", progress.written_today, &[("count", &progress.written_today.to_string())])));
+	pace.add(&metric_row(&i18n("Recent Pace"), i18n_f("{count} words a day", &[("count", &format!("{:.0}", progress.recent_pace))])));
+	if let Some(required) = progress.required_pace() {
+		pace.add(&metric_row(&i18n("Required Pace"), ni18n_f("{count} word a day", "{count} words a day", required, &[("count", &required.to_string())])));
+	}
+	if let Some(finish) = progress.projected_finish() {
+		pace.add(&metric_row(&i18n("Projected Finish"), format_day(&finish)));
+	}
+	page.add(&pace);
+
+	page
+}
@@ -0,0 +1,313 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	fs,
+	path::PathBuf,
+};
+
+use relm4::{
+	gtk, gtk::prelude::*, gtk::glib,
+};
+
+use crate::store;
+
+/// Number of weeks shown in the activity heatmap of the Home page.
+pub(super) const HEATMAP_WEEKS: usize = 20;
+
+fn history_path() -> PathBuf {
+	glib::user_data_dir().join("austeur").join("history.json")
+}
+
+/// Today as `YYYY-MM-DD`, in local time.
+pub(super) fn today() -> String {
+	day_offset(0)
+}
+
+/// The day `offset` days from today, as `YYYY-MM-DD`.
+fn day_offset(offset: i32) -> String {
+	glib::DateTime::now_local()
+		.and_then(|now| now.add_days(offset))
+		.and_then(|day| day.format("%Y-%m-%d"))
+		.map(|day| day.to_string())
+		.unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct DayRecord {
+	pub(super) written: usize,
+	pub(super) deleted: usize,
+}
+
+/// The outcome of a writing sprint.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct SprintRecord {
+	pub(super) day: String,
+	/// The id of the writing or project sprinted on.
+	pub(super) id: String,
+	pub(super) seconds: u32,
+	pub(super) words: usize,
+	pub(super) target: Option<usize>,
+}
+
+impl SprintRecord {
+	fn from_json(value: &json::JsonValue) -> Option<Self> {
+		Some(Self {
+			day: value["day"].as_str()?.to_string(),
+			id: value["writing"].as_str()?.to_string(),
+			seconds: value["seconds"].as_u32()?,
+			words: value["words"].as_usize().unwrap_or_default(),
+			target: value["target"].as_usize(),
+		})
+	}
+
+	fn to_json(&self) -> json::JsonValue {
+		let mut value = json::object! {
+			day: self.day.as_str(),
+			writing: self.id.as_str(),
+			seconds: self.seconds,
+			words: self.words,
+		};
+		if let Some(target) = self.target {
+			value["target"] = target.into();
+		}
+		value
+	}
+
+	pub(super) fn words_per_minute(&self) -> f64 {
+		self.words as f64 * 60.0 / self.seconds.max(1) as f64
+	}
+}
+
+/// Words written and deleted per day and per writing, and past sprints.
+#[derive(Debug, Clone, Default)]
+pub(super) struct History {
+	/// Keyed by day, then by the id of the writing or project.
+	days: BTreeMap<String, HashMap<String, DayRecord>>,
+	sprints: Vec<SprintRecord>,
+	/// The day the daily goal was last celebrated, so it is only once a day.
+	goal_notified_on: Option<String>,
+}
+
+impl History {
+	pub(super) fn load() -> Self {
+		let Some(parsed) = fs::read_to_string(history_path()).ok()
+			.and_then(|contents| json::parse(contents.as_str()).ok()) else {
+			return Self::default();
+		};
+		let days = parsed["days"].entries()
+			.map(|(day, writings)| {
+				let writings = writings.entries()
+					.map(|(id, record)| (id.to_string(), DayRecord {
+						written: record["written"].as_usize().unwrap_or_default(),
+						deleted: record["deleted"].as_usize().unwrap_or_default(),
+					}))
+					.collect();
+				(day.to_string(), writings)
+			})
+			.collect();
+		let sprints = parsed["sprints"].members()
+			.filter_map(SprintRecord::from_json)
+			.collect();
+		Self {
+			days,
+			sprints,
+			goal_notified_on: parsed["goal_notified_on"].as_str().map(String::from),
+		}
+	}
+
+	pub(super) fn save(&self) -> std::io::Result<()> {
+		let mut days = json::JsonValue::new_object();
+		for (day, writings) in self.days.iter() {
+			let mut day_value = json::JsonValue::new_object();
+			for (id, record) in writings.iter() {
+				day_value[id.as_str()] = json::object! {
+					written: record.written,
+					deleted: record.deleted,
+				};
+			}
+			days[day.as_str()] = day_value;
+		}
+		let sprints: Vec<json::JsonValue> = self.sprints.iter().map(SprintRecord::to_json).collect();
+		let mut value = json::object! {
+			days: days,
+			sprints: sprints,
+		};
+		if let Some(day) = &self.goal_notified_on {
+			value["goal_notified_on"] = day.as_str().into();
+		}
+		let path = history_path();
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+		store::write_atomically(&path, json::stringify(value).as_bytes())
+	}
+
+	/// Adds a change in word count of the writing `id` to today's record.
+	pub(super) fn record(&mut self, id: &str, delta: isize) {
+		if delta == 0 {
+			return;
+		}
+		let record = self.days.entry(today()).or_default().entry(id.to_string()).or_default();
+		if delta > 0 {
+			record.written += delta as usize;
+		} else {
+			record.deleted += delta.unsigned_abs();
+		}
+	}
+
+	/// Carries the records of the writing `from` over to the writing `to`.
+	pub(super) fn merge(&mut self, from: &str, to: &str) {
+		for writings in self.days.values_mut() {
+			if let Some(record) = writings.remove(from) {
+				let target = writings.entry(to.to_string()).or_default();
+				target.written += record.written;
+				target.deleted += record.deleted;
+			}
+		}
+		for sprint in self.sprints.iter_mut().filter(|sprint| sprint.id == from) {
+			sprint.id = to.to_string();
+		}
+	}
+
+	/// Carries `share` of the records of the writing `from` over to the
+	/// writing `to`, such as when a part of it is split off. Sprints stay.
+	pub(super) fn split(&mut self, from: &str, to: &str, share: f64) {
+		let share = share.clamp(0.0, 1.0);
+		let part = |count: usize| (count as f64 * share).round() as usize;
+		for writings in self.days.values_mut() {
+			let Some(record) = writings.get_mut(from) else {
+				continue;
+			};
+			let moved = DayRecord {
+				written: part(record.written),
+				deleted: part(record.deleted),
+			};
+			if moved == DayRecord::default() {
+				continue;
+			}
+			record.written -= moved.written;
+			record.deleted -= moved.deleted;
+			let target = writings.entry(to.to_string()).or_default();
+			target.written += moved.written;
+			target.deleted += moved.deleted;
+		}
+	}
+
+	pub(super) fn sprints(&self) -> &[SprintRecord] {
+		&self.sprints
+	}
+
+	pub(super) fn record_sprint(&mut self, sprint: SprintRecord) {
+		self.sprints.push(sprint);
+	}
+
+	pub(super) fn is_goal_notified_today(&self) -> bool {
+		self.goal_notified_on.as_deref() == Some(today().as_str())
+	}
+
+	pub(super) fn set_goal_notified_today(&mut self) {
+		self.goal_notified_on = Some(today());
+	}
+
+	/// Words written today in the writing `id`.
+	pub(super) fn written_today(&self, id: &str) -> usize {
+		self.days.get(&today())
+			.and_then(|writings| writings.get(id))
+			.map(|record| record.written)
+			.unwrap_or_default()
+	}
+
+	/// Average of words written minus words deleted per day in the writing
+	/// `id`, over the last `count` days.
+	pub(super) fn average_net(&self, id: &str, count: usize) -> f64 {
+		let net: isize = (0..count as i32)
+			.filter_map(|offset| self.days.get(&day_offset(-offset)))
+			.filter_map(|writings| writings.get(id))
+			.map(|record| record.written as isize - record.deleted as isize)
+			.sum();
+		net as f64 / count.max(1) as f64
+	}
+
+	fn day_total(&self, day: &str) -> DayRecord {
+		self.days.get(day)
+			.map(|writings| writings.values().fold(DayRecord::default(), |acc, record| DayRecord {
+				written: acc.written + record.written,
+				deleted: acc.deleted + record.deleted,
+			}))
+			.unwrap_or_default()
+	}
+
+	/// Totals of all writings for the last `count` days, oldest first.
+	pub(super) fn recent_days(&self, count: usize) -> Vec<(String, DayRecord)> {
+		(0..count as i32).rev()
+			.map(|offset| {
+				let day = day_offset(-offset);
+				let total = self.day_total(&day);
+				(day, total)
+			})
+			.collect()
+	}
+
+	/// Number of consecutive days with words written, up to today. A streak is
+	/// not broken before the end of today.
+	pub(super) fn streak(&self) -> usize {
+		let written = |offset: i32| self.day_total(&day_offset(-offset)).written > 0;
+		let start = if written(0) { 0 } else { 1 };
+		(start..).take_while(|offset| written(*offset)).count()
+	}
+
+	/// The history as CSV, with the titles of the writings and projects as
+	/// `titles` has them by id.
+	pub(super) fn to_csv(&self, titles: &HashMap<String, String>) -> String {
+		let mut csv = String::from("date,writing,title,written,deleted\n");
+		for (day, writings) in self.days.iter() {
+			let mut writings: Vec<_> = writings.iter().collect();
+			writings.sort_by(|a, b| a.0.cmp(b.0));
+			for (id, record) in writings {
+				let title = titles.get(id).map_or("", String::as_str);
+				csv.push_str(&format!("{},{},{},{},{}\n", day, id, csv_field(title), record.written, record.deleted));
+			}
+		}
+		csv
+	}
+
+	/// The last `count` days as JSON, for the charts of the History page.
+	pub(super) fn to_chart_json(&self, count: usize) -> String {
+		let days: Vec<json::JsonValue> = self.recent_days(count).into_iter()
+			.map(|(day, record)| json::object! {
+				day: day,
+				written: record.written,
+				deleted: record.deleted,
+			})
+			.collect();
+		json::stringify(json::object! {
+			days: days,
+			streak: self.streak(),
+		})
+	}
+}
+
+/// Quotes a field of CSV holding separators or quotes.
+fn csv_field(text: &str) -> String {
+	if text.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", text.replace('"', "\"\""))
+	} else {
+		text.to_string()
+	}
+}
+
+/// Draws a square per day, a column per week, shaded by the words written.
+pub(super) fn draw_heatmap(activity: Vec<usize>) -> impl Fn(&gtk::DrawingArea, &gtk::cairo::Context, i32, i32) {
+	move |area, cr, width, height| {
+		let color = area.color();
+		let columns = activity.len().div_ceil(7).max(1);
+		let cell = (width as f64 / columns as f64).min(height as f64 / 7.0);
+		let most = activity.iter().copied().max().unwrap_or_default().max(1);
+		for (index, written) in activity.iter().enumerate() {
+			let (x, y) = ((index / 7) as f64 * cell, (index % 7) as f64 * cell);
+			let alpha = if *written == 0 { 0.08 } else { 0.25 + 0.75 * (*written as f64 / most as f64) };
+			cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, alpha);
+			cr.rectangle(x + 1.0, y + 1.0, cell - 2.0, cell - 2.0);
+			let _ = cr.fill();
+		}
+	}
+}
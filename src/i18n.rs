@@ -1,5 +1,23 @@
-use gettextrs::gettext;
+use gettextrs::{gettext, ngettext};
 
 pub fn i18n(format: &str) -> String {
 	gettext(format)
 }
+
+/// Translates `format` and fills in its named placeholders, such as `{count}`,
+/// so that translators can move them around the sentence.
+pub fn i18n_f(format: &str, args: &[(&str, &str)]) -> String {
+	fill(gettext(format), args)
+}
+
+/// Like `i18n_f`, with the singular or a plural form as `n` calls for.
+pub fn ni18n_f(singular: &str, plural: &str, n: usize, args: &[(&str, &str)]) -> String {
+	fill(ngettext(singular, plural, n.min(u32::MAX as usize) as u32), args)
+}
+
+fn fill(mut text: String, args: &[(&str, &str)]) -> String {
+	for (name, value) in args {
+		text = text.replace(&format!("{{{}}}", name), value);
+	}
+	text
+}
@@ -2,11 +2,33 @@
 //
 // SPDX-License-Identifier: MIT
 
+mod analyzer;
 mod app;
+mod binder;
 #[rustfmt::skip]
 mod config;
+mod continuation;
+mod corkboard;
+mod document;
+mod focus;
+mod formatting;
+mod frequency;
+mod goals;
+mod history;
 mod i18n;
+mod preview;
+mod punctuation;
+mod rhythm;
+mod sprint;
+mod stats;
+mod store;
+mod templates;
+mod theme;
 mod toc;
+mod typewriter;
+mod typography;
+mod wordcount;
+mod wysiwym;
 
 use gettextrs::{gettext, LocaleCategory};
 
@@ -47,14 +69,27 @@ fn main() -> () {
     let history_html = res
     	.lookup_data("/com/github/kinten108101/Austeur/history-index.html", gio::ResourceLookupFlags::NONE)
     	.unwrap();
+    let analysis_html = res
+    	.lookup_data("/com/github/kinten108101/Austeur/analysis-index.html", gio::ResourceLookupFlags::NONE)
+    	.unwrap();
+    let preview_html = res
+    	.lookup_data("/com/github/kinten108101/Austeur/preview-index.html", gio::ResourceLookupFlags::NONE)
+    	.unwrap();
 
     let text_style_manager = sourceview5::StyleSchemeManager::default();
-	text_style_manager.set_search_path(&[PKGDATADIR,]);
+	let user_styles_dir = theme::user_styles_dir().to_string_lossy().to_string();
+	text_style_manager.set_search_path(&[PKGDATADIR, user_styles_dir.as_str()]);
+
+    let language_manager = sourceview5::LanguageManager::default();
+    let mut language_path: Vec<String> = language_manager.search_path().iter().map(|path| path.to_string()).collect();
+    language_path.push(PKGDATADIR.to_string());
+    language_manager.set_search_path(&language_path.iter().map(String::as_str).collect::<Vec<_>>());
+    let markdown = language_manager.language("austeur-markdown");
 
     gtk::Window::set_default_icon_name(APP_ID);
 
     let app = main_application();
     app.set_resource_base_path(Some("/com/github/kinten108101/Austeur"));
     let app = RelmApp::from_app(app);
-    app.run::<App>((SidebarPage::Sections, text_style_manager, WindowPage::Home, history_html));
+    app.run::<App>((SidebarPage::Sections, text_style_manager, WindowPage::Home, history_html, analysis_html, preview_html, markdown));
 }
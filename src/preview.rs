@@ -0,0 +1,90 @@
+use std::{
+	collections::HashMap,
+	ops::Range,
+};
+
+use pulldown_cmark::{
+	Event, Tag,
+};
+
+use crate::document::Document;
+
+/// Anchor of the lines before the first heading.
+const PREAMBLE_ANCHOR: &str = "preamble";
+
+/// The line ranges of the preamble and of each section, with their anchors.
+/// Sections are anchored by position, so that the text and the preview only
+/// disagree about them until the preview is rendered again.
+fn anchored_ranges(document: &Document) -> Vec<(String, Range<usize>)> {
+	let ranges = document.section_ranges();
+	let first_heading = ranges.first().map(|range| range.start).unwrap_or(document.line_count());
+	std::iter::once((PREAMBLE_ANCHOR.to_string(), 0..first_heading))
+		.chain(ranges.into_iter().enumerate().map(|(index, range)| (format!("section-{}", index), range)))
+		.collect()
+}
+
+/// The document as HTML, with the anchor of each section on its
+/// heading, so the preview can be scrolled to any of them. The document is
+/// rendered whole, for footnotes and links to find their definitions.
+pub(super) fn render(document: &Document) -> String {
+	let mut markdown = String::new();
+	// Where each line starts in `markdown`, with its index.
+	let mut lines = Vec::new();
+	for (index, text) in document.markdown_lines() {
+		lines.push((markdown.len(), index));
+		markdown.push_str(text);
+		markdown.push('\n');
+	}
+	let sections: HashMap<usize, usize> = document.section_ranges().into_iter()
+		.enumerate()
+		.map(|(section, range)| (range.start, section))
+		.collect();
+	let options = pulldown_cmark::Options::ENABLE_STRIKETHROUGH
+		| pulldown_cmark::Options::ENABLE_TABLES
+		| pulldown_cmark::Options::ENABLE_FOOTNOTES;
+	let events = pulldown_cmark::Parser::new_ext(&markdown, options).into_offset_iter()
+		.map(|(event, range)| match event {
+			Event::Start(Tag::Heading { level, id: None, classes, attrs }) => {
+				let line = lines[lines.partition_point(|(start, _)| *start <= range.start).saturating_sub(1)].1;
+				let id = sections.get(&line).map(|section| format!("section-{}", section).into());
+				Event::Start(Tag::Heading { level, id, classes, attrs })
+			},
+			event => event,
+		});
+	let mut html = format!("<div id=\"{}\"></div>", PREAMBLE_ANCHOR);
+	pulldown_cmark::html::push_html(&mut html, events);
+	html
+}
+
+/// Where `line` falls in the preview: the anchor of its section, and how far
+/// into the section it is, from 0 to 1.
+pub(super) fn position(document: &Document, line: usize) -> (String, f64) {
+	anchored_ranges(document).into_iter()
+		.find(|(_, range)| range.contains(&line))
+		.map(|(anchor, range)| {
+			let fraction = (line - range.start) as f64 / range.len().max(1) as f64;
+			(anchor, fraction)
+		})
+		.unwrap_or_else(|| (PREAMBLE_ANCHOR.to_string(), 0.0))
+}
+
+/// Script replacing the content of the preview page with `html`.
+pub(super) fn content_script(html: &str) -> String {
+	format!("document.getElementById(\"content\").innerHTML = {};", json::stringify(html))
+}
+
+/// Script replacing the typography of the preview page with `css`.
+pub(super) fn style_script(css: &str) -> String {
+	format!("document.getElementById(\"typography\").textContent = {};", json::stringify(css))
+}
+
+/// Script scrolling the preview page to the given position, a section
+/// spanning from its anchor to the next one.
+pub(super) fn scroll_script(anchor: &str, fraction: f64) -> String {
+	format!(
+		"(() => {{ const anchors = [...document.querySelectorAll('#{}, [id^=\"section-\"]')]; const index = anchors.findIndex((element) => element.id === {}); if (index < 0) {{ return; }} const top = anchors[index].offsetTop; const bottom = index + 1 < anchors.length ? anchors[index + 1].offsetTop : document.documentElement.scrollHeight; window.scrollTo(0, top + (bottom - top) * {}); }})();",
+		PREAMBLE_ANCHOR,
+		json::stringify(anchor),
+		fraction,
+	)
+}
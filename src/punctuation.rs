@@ -0,0 +1,242 @@
+use relm4::gtk::glib;
+
+use crate::{
+	document::{
+		Document, split_lines,
+	},
+	wordcount::CountingRules,
+};
+
+const NO_BREAK_SPACE: char = '\u{a0}';
+const NARROW_NO_BREAK_SPACE: char = '\u{202f}';
+
+lazy_static::lazy_static! {
+	static ref LIST_ITEM_PATTERN: regex::Regex = regex::Regex::new(r"^ {0,3}(?:[-*+]|\d{1,9}[.)])(?:[ \t]|$)").unwrap();
+}
+
+/// Typographic conventions of a language, as far as punctuation goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Locale {
+	English,
+	French,
+	German,
+}
+
+impl Locale {
+	pub(super) const ALL: [Locale; 3] = [Locale::English, Locale::French, Locale::German];
+
+	pub(super) fn id(&self) -> &'static str {
+		match self {
+			Locale::English => "en",
+			Locale::French => "fr",
+			Locale::German => "de",
+		}
+	}
+
+	/// The conventions of a language tag such as `fr`, `de-CH` or `en_GB.UTF-8`,
+	/// falling back to English ones.
+	pub(super) fn from_language(language: &str) -> Self {
+		let code = language.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+		Locale::ALL.into_iter().find(|locale| locale.id() == code).unwrap_or(Locale::English)
+	}
+
+	/// The conventions of the language of the user.
+	pub(super) fn system() -> Self {
+		glib::language_names().first()
+			.map(|language| Self::from_language(language))
+			.unwrap_or(Locale::English)
+	}
+
+	fn double_quotes(&self) -> (&'static str, &'static str) {
+		match self {
+			Locale::English => ("“", "”"),
+			Locale::French => ("«\u{a0}", "\u{a0}»"),
+			Locale::German => ("„", "“"),
+		}
+	}
+
+	fn single_quotes(&self) -> (&'static str, &'static str) {
+		match self {
+			Locale::English => ("‘", "’"),
+			Locale::French => ("‹\u{a0}", "\u{a0}›"),
+			Locale::German => ("‚", "‘"),
+		}
+	}
+}
+
+/// A substitution of a typed character: the characters before it to replace
+/// along with it, and what they are replaced by.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Substitution {
+	pub(super) replaced: usize,
+	pub(super) text: String,
+}
+
+impl Substitution {
+	fn new(replaced: usize, text: impl Into<String>) -> Option<Self> {
+		Some(Self { replaced, text: text.into() })
+	}
+}
+
+/// Whether a quote after `previous` opens rather than closes, such as after
+/// a space or the markers of emphasis.
+fn opens_quote(previous: Option<char>) -> bool {
+	match previous {
+		Some(c) => c.is_whitespace() || "([{—–-/“‘„‚«‹*_~".contains(c),
+		None => true,
+	}
+}
+
+/// Whether the line is code so far, as in an unclosed code span, where
+/// nothing is to be replaced.
+fn is_code(before: &str) -> bool {
+	before.matches('`').count() % 2 == 1
+}
+
+fn is_indented(line: &str) -> bool {
+	line.starts_with("    ") || line.starts_with('\t')
+}
+
+/// Whether `line` is part of an indented code block, given the lines before it
+/// from the nearest on. Indented lines that go on with a list item or a
+/// paragraph are not code.
+pub(super) fn is_in_code_block(line: &str, lines_before: impl Iterator<Item = impl AsRef<str>>) -> bool {
+	if !is_indented(line) {
+		return false;
+	}
+	let mut is_after_blank = false;
+	for previous in lines_before {
+		let previous = previous.as_ref();
+		if previous.trim().is_empty() {
+			is_after_blank = true;
+		} else if !is_indented(previous) {
+			return !LIST_ITEM_PATTERN.is_match(previous) && (is_after_blank || previous.starts_with('#'));
+		}
+	}
+	true
+}
+
+/// Whether the line so far is within a link target or a comment, whose
+/// hyphens and punctuation are not prose.
+fn is_markup(before: &str) -> bool {
+	let is_in_comment = before.rfind("<!--").is_some_and(|open| !before[open..].contains("-->"));
+	let is_in_target = before.rfind("](").is_some_and(|open| !before[open..].contains(')'));
+	let is_in_url = before.split_whitespace().last().is_some_and(|word| word.contains("://"));
+	is_in_comment || is_in_target || is_in_url || before.ends_with("<!-")
+}
+
+/// A quote, which replaces the space before it if it closes with one of its own.
+fn quote(locale: Locale, before: &str, (open, close): (&str, &str)) -> Option<Substitution> {
+	let previous = before.chars().last();
+	if opens_quote(previous) {
+		return Substitution::new(0, open);
+	}
+	if close.starts_with(NO_BREAK_SPACE) && previous == Some(' ') && locale == Locale::French {
+		return Substitution::new(1, close);
+	}
+	Substitution::new(0, close)
+}
+
+/// The substitution of `typed` after the text `before` it on its line, if any.
+pub(super) fn substitute(locale: Locale, before: &str, typed: char) -> Option<Substitution> {
+	if is_code(before) || is_markup(before) {
+		return None;
+	}
+	let previous = before.chars().last();
+	match typed {
+		'"' => quote(locale, before, locale.double_quotes()),
+		// An apostrophe, unless it opens or closes a quote.
+		'\'' if previous.is_some_and(char::is_alphanumeric) => Substitution::new(0, "’"),
+		'\'' => quote(locale, before, locale.single_quotes()),
+		// Not in rules and front matter, which are made of hyphens alone.
+		'-' if previous == Some('-') && !before.trim().chars().all(|c| c == '-') => Substitution::new(1, "—"),
+		'.' if before.ends_with("..") && !before.ends_with("...") => Substitution::new(2, "…"),
+		';' | ':' | '!' | '?' if locale == Locale::French => {
+			let space = if typed == ':' { NO_BREAK_SPACE } else { NARROW_NO_BREAK_SPACE };
+			match previous {
+				Some(' ') => Substitution::new(1, format!("{}{}", space, typed)),
+				// Times such as 10:30 go without a space.
+				Some(c) if typed != ':' && c.is_alphanumeric() => Substitution::new(0, format!("{}{}", space, typed)),
+				Some(c) if typed != ':' && "»”)".contains(c) => Substitution::new(0, format!("{}{}", space, typed)),
+				_ => None,
+			}
+		},
+		_ => None,
+	}
+}
+
+/// Applies the substitutions to existing text, as if it had been typed,
+/// leaving out front matter, comments and code blocks.
+pub(super) fn convert(text: &str, locale: Locale) -> String {
+	// Only the regions of the text matter, which the rules of counting don't change.
+	let document = Document::new(text, CountingRules::default());
+	let lines = split_lines(text);
+	let mut converted = Vec::new();
+	for (index, &line) in lines.iter().enumerate() {
+		if is_in_code_block(line, lines[..index].iter().rev()) {
+			converted.push(line.to_string());
+			continue;
+		}
+		let mut output = String::new();
+		for c in line.chars() {
+			let substitution = Some(c)
+				.filter(|_| document.is_prose_at(index, &output))
+				.and_then(|c| substitute(locale, &output, c));
+			match substitution {
+				Some(substitution) => {
+					let keep = output.chars().count() - substitution.replaced;
+					output = output.chars().take(keep).collect();
+					output.push_str(&substitution.text);
+				},
+				None => output.push(c),
+			}
+		}
+		converted.push(output);
+	}
+	converted.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Locale, convert};
+
+	fn english(text: &str) -> String {
+		convert(text, Locale::English)
+	}
+
+	#[test]
+	fn punctuates_english() {
+		assert_eq!(english("\"Hi,\" she said -- it's 'done'..."), "“Hi,” she said — it’s ‘done’…");
+		assert_eq!(english("*\"Hi\"* and _'there'_"), "*“Hi”* and _‘there’_");
+	}
+
+	#[test]
+	fn punctuates_french() {
+		assert_eq!(
+			convert("\"Bonjour\" : oui ! Non ? Il est 10:30; 'ah !'", Locale::French),
+			"«\u{a0}Bonjour\u{a0}»\u{a0}: oui\u{202f}! Non\u{202f}? Il est 10:30\u{202f}; ‹\u{a0}ah\u{202f}!\u{a0}›",
+		);
+	}
+
+	#[test]
+	fn punctuates_german() {
+		assert_eq!(convert("\"Hallo\", sagte sie. 'Ja!' Es geht's", Locale::German), "„Hallo“, sagte sie. ‚Ja!‘ Es geht’s");
+	}
+
+	#[test]
+	fn leaves_code_and_markup_alone() {
+		assert_eq!(english("Run `\"x\" -- y` now"), "Run `\"x\" -- y` now");
+		assert_eq!(english("See [it](http://a.org/x--y) <!-- \"note\" -->"), "See [it](http://a.org/x--y) <!-- \"note\" -->");
+		assert_eq!(english("---\ntitle: \"A\"\n---\n\"B\""), "---\ntitle: \"A\"\n---\n“B”");
+		assert_eq!(english("<!--\n\"A\"\n--> \"B\""), "<!--\n\"A\"\n--> “B”");
+		assert_eq!(english("```\n\"A\" -- b\n```\n\"B\""), "```\n\"A\" -- b\n```\n“B”");
+		assert_eq!(english("Text\n\n---"), "Text\n\n---");
+	}
+
+	#[test]
+	fn tells_indented_code_from_lists() {
+		assert_eq!(english("Text\n\n    \"code\""), "Text\n\n    \"code\"");
+		assert_eq!(english("- Item\n\n    \"more\""), "- Item\n\n    “more”");
+		assert_eq!(english("Text\n    \"more\""), "Text\n    “more”");
+	}
+}
@@ -0,0 +1,100 @@
+use crate::{
+	document::Document,
+	stats::sentences,
+	wordcount::count_words,
+};
+
+/// Paragraphs of at least this many sentences can be called monotonous.
+const MIN_MONOTONOUS_SENTENCES: usize = 3;
+/// Below this spread of lengths, in words, sentences all sound alike.
+const MONOTONOUS_DEVIATION: f64 = 2.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Sentence {
+	/// Where the sentence starts, as a line and a character offset in it.
+	pub(super) line: usize,
+	pub(super) offset: usize,
+	pub(super) words: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Paragraph {
+	pub(super) sentences: Vec<Sentence>,
+}
+
+impl Paragraph {
+	fn deviation(&self) -> f64 {
+		let count = self.sentences.len().max(1) as f64;
+		let mean = self.sentences.iter().map(|sentence| sentence.words as f64).sum::<f64>() / count;
+		let variance = self.sentences.iter()
+			.map(|sentence| (sentence.words as f64 - mean).powi(2))
+			.sum::<f64>() / count;
+		variance.sqrt()
+	}
+
+	pub(super) fn is_monotonous(&self) -> bool {
+		self.sentences.len() >= MIN_MONOTONOUS_SENTENCES && self.deviation() < MONOTONOUS_DEVIATION
+	}
+}
+
+/// Sentences of the prose of the document, paragraph by paragraph. Blank
+/// lines and headings end paragraphs.
+pub(super) fn paragraphs(document: &Document) -> Vec<Paragraph> {
+	let rules = document.rules();
+	let mut paragraphs = vec![];
+	// The paragraph being read: its characters, with lines running on into
+	// each other, and where each of its lines starts in them.
+	let mut chars: Vec<char> = vec![];
+	let mut line_starts: Vec<(usize, usize)> = vec![];
+
+	let mut finish_paragraph = |chars: &mut Vec<char>, line_starts: &mut Vec<(usize, usize)>| {
+		let sentences: Vec<Sentence> = sentences(chars).into_iter()
+			.filter_map(|range| {
+				let words = count_words(&chars[range.clone()].iter().collect::<String>(), &rules);
+				let (line, line_start) = line_starts.iter()
+					.rev()
+					.find(|(_, line_start)| *line_start <= range.start)
+					.copied()?;
+				(words > 0).then_some(Sentence { line, offset: range.start - line_start, words })
+			})
+			.collect();
+		if !sentences.is_empty() {
+			paragraphs.push(Paragraph { sentences });
+		}
+		chars.clear();
+		line_starts.clear();
+	};
+
+	for (index, text) in document.prose_lines() {
+		if text.trim().is_empty() || text.trim_start().starts_with('#') {
+			finish_paragraph(&mut chars, &mut line_starts);
+			continue;
+		}
+		line_starts.push((index, chars.len()));
+		chars.extend(text.chars());
+		chars.push('\n');
+	}
+	finish_paragraph(&mut chars, &mut line_starts);
+	paragraphs
+}
+
+pub(super) fn to_json(paragraphs: &[Paragraph]) -> String {
+	let paragraphs: Vec<json::JsonValue> = paragraphs.iter()
+		.map(|paragraph| {
+			let sentences: Vec<json::JsonValue> = paragraph.sentences.iter()
+				.map(|sentence| json::object! {
+					line: sentence.line,
+					offset: sentence.offset,
+					words: sentence.words,
+				})
+				.collect();
+			json::object! {
+				sentences: sentences,
+				monotonous: paragraph.is_monotonous(),
+			}
+		})
+		.collect();
+	json::stringify(json::object! {
+		paragraphs: paragraphs,
+	})
+}
@@ -0,0 +1,104 @@
+use relm4::gtk::glib;
+
+use crate::{
+	history::{
+		SprintRecord, today,
+	},
+	i18n::{
+		i18n, i18n_f, ni18n_f,
+	},
+};
+
+/// A timed burst of writing, counting the words added since it started.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Sprint {
+	/// Length of the sprint, in seconds.
+	duration: u32,
+	/// Monotonic time the sprint started at, in microseconds.
+	started: i64,
+	/// Seconds elapsed as of the last tick.
+	elapsed: u32,
+	target: Option<usize>,
+	start_word_count: usize,
+	words: usize,
+}
+
+impl Sprint {
+	pub(super) fn new(minutes: u32, target: Option<usize>, word_count: usize) -> Self {
+		Self {
+			duration: minutes * 60,
+			started: glib::monotonic_time(),
+			elapsed: 0,
+			target,
+			start_word_count: word_count,
+			words: 0,
+		}
+	}
+
+	/// Catches up with the clock, returning whether the sprint is over. Ticks
+	/// may come late, so the time is read rather than counted.
+	pub(super) fn tick(&mut self, word_count: usize) -> bool {
+		let seconds = (glib::monotonic_time() - self.started) / 1_000_000;
+		self.elapsed = (seconds.max(0) as u32).min(self.duration);
+		self.words = word_count.saturating_sub(self.start_word_count);
+		self.elapsed >= self.duration
+	}
+
+	/// Time left, as `mm:ss`.
+	pub(super) fn countdown(&self) -> String {
+		let remaining = self.duration.saturating_sub(self.elapsed);
+		format!("{:02}:{:02}", remaining / 60, remaining % 60)
+	}
+
+	pub(super) fn progress_label(&self) -> String {
+		match self.target {
+			Some(target) => ni18n_f("{count} / {target} word", "{count} / {target} words", target, &[("count", &self.words.to_string()), ("target", &target.to_string())]),
+			None => ni18n_f("{count} word", "{count} words", self.words, &[("count", &self.words.to_string())]),
+		}
+	}
+
+	pub(super) fn record(&self, id: String) -> SprintRecord {
+		SprintRecord {
+			day: today(),
+			id,
+			seconds: self.elapsed,
+			words: self.words,
+			target: self.target,
+		}
+	}
+}
+
+/// What to tell the writer once a sprint is over, measured against the
+/// sprints that came before it.
+pub(super) fn summary(sprint: &SprintRecord, past: &[SprintRecord]) -> String {
+	let mut lines = vec![
+		ni18n_f("{count} word in {minutes} min", "{count} words in {minutes} min", sprint.words, &[("count", &sprint.words.to_string()), ("minutes", &(sprint.seconds as f64 / 60.0).round().to_string())]),
+		i18n_f("{count} words per minute", &[("count", &format!("{:.1}", sprint.words_per_minute()))]),
+	];
+	if let Some(target) = sprint.target {
+		if sprint.words >= target {
+			lines.push(i18n("Target reached!"));
+		} else {
+			let short = target - sprint.words;
+			lines.push(ni18n_f("{count} word short of the target", "{count} words short of the target", short, &[("count", &short.to_string())]));
+		}
+	}
+	if past.is_empty() {
+		lines.push(i18n("This is your first sprint."));
+	} else {
+		let average = past.iter().map(SprintRecord::words_per_minute).sum::<f64>() / past.len() as f64;
+		let best = past.iter().map(SprintRecord::words_per_minute).fold(0.0, f64::max);
+		lines.push(ni18n_f(
+			"Your average is {average} words per minute over {count} sprint",
+			"Your average is {average} words per minute over {count} sprints",
+			past.len(),
+			&[("average", &format!("{:.1}", average)), ("count", &past.len().to_string())],
+		));
+		if sprint.words_per_minute() > best {
+			lines.push(i18n("A new personal best!"));
+		} else {
+			lines.push(i18n_f("Your best is {best} words per minute", &[("best", &format!("{:.1}", best))]));
+		}
+	}
+	lines.join("\n")
+}
@@ -0,0 +1,284 @@
+use std::{
+	collections::HashSet,
+	ops::Range,
+};
+
+use relm4::{
+	adw, adw::prelude::*,
+	gtk, gtk::prelude::*,
+};
+
+use crate::{
+	document::Document,
+	i18n::{
+		i18n, i18n_f,
+	},
+	wordcount::CountingRules,
+};
+
+/// Average silent reading speed of adults, in words per minute.
+const READING_WPM: f64 = 238.0;
+/// Typical pace of actors delivering dialogue, in words per minute.
+const SPEAKING_WPM: f64 = 150.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum StatScope {
+	Document,
+	Section,
+	Selection,
+}
+
+impl StatScope {
+	fn id(&self) -> &'static str {
+		match self {
+			StatScope::Document => "document",
+			StatScope::Section => "section",
+			StatScope::Selection => "selection",
+		}
+	}
+
+	fn label(&self) -> String {
+		match self {
+			StatScope::Document => i18n("Document"),
+			StatScope::Section => i18n("Section"),
+			StatScope::Selection => i18n("Selection"),
+		}
+	}
+
+	fn icon_name(&self) -> &'static str {
+		match self {
+			StatScope::Document => "paper-symbolic",
+			StatScope::Section => "text-justify-left-symbolic",
+			StatScope::Selection => "edit-select-all-symbolic",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct TextStats {
+	pub(super) words: usize,
+	pub(super) characters: usize,
+	pub(super) characters_no_spaces: usize,
+	pub(super) sentences: usize,
+	pub(super) paragraphs: usize,
+	pub(super) unique_words: usize,
+	pub(super) syllables: usize,
+	/// Words of three syllables or more.
+	pub(super) complex_words: usize,
+}
+
+/// Words that end in a period without ending a sentence.
+const ABBREVIATIONS: [&str; 13] = ["mr", "mrs", "ms", "dr", "prof", "st", "jr", "sr", "vs", "e.g", "i.e", "cf", "mt"];
+
+/// Whether the period after `before` ends an abbreviation, such as "Mr." or an
+/// initial, rather than a sentence. "I" is taken for the pronoun.
+fn is_abbreviation(before: &[char]) -> bool {
+	let start = before.iter()
+		.rposition(|c| !(c.is_alphabetic() || *c == '.'))
+		.map_or(0, |index| index + 1);
+	let word: String = before[start..].iter().collect();
+	let mut chars = word.chars();
+	let is_initial = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_uppercase() && c != 'I');
+	is_initial || ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+fn is_terminator(c: char) -> bool {
+	matches!(c, '.' | '!' | '?' | '…' | '。' | '！' | '？')
+}
+
+/// Closing quotes and brackets still belong to the sentence they end.
+fn is_closing(c: char) -> bool {
+	matches!(c, '"' | '\'' | '”' | '’' | ')' | ']' | '»' | '」' | '』')
+}
+
+/// Sentences of a text, as ranges of its characters. A sentence ends in
+/// punctuation followed by a space or the end of the text, so that decimals
+/// and abbreviations don't end one. Full-width punctuation needs no space.
+pub(super) fn sentences(chars: &[char]) -> Vec<Range<usize>> {
+	let mut sentences = vec![];
+	let mut start = None;
+	let mut i = 0;
+	while i < chars.len() {
+		let c = chars[i];
+		if start.is_none() && !c.is_whitespace() {
+			start = Some(i);
+		}
+		if !is_terminator(c) {
+			i += 1;
+			continue;
+		}
+		let mut end = i + 1;
+		while end < chars.len() && (is_terminator(chars[end]) || is_closing(chars[end])) {
+			end += 1;
+		}
+		let is_end = matches!(c, '。' | '！' | '？') || end == chars.len() || chars[end].is_whitespace();
+		let is_abbreviated = c == '.' && end == i + 1 && is_abbreviation(&chars[..i]);
+		if is_end && !is_abbreviated {
+			if let Some(start) = start.take() {
+				sentences.push(start..end);
+			}
+		}
+		i = end;
+	}
+	if let Some(start) = start {
+		let end = chars.iter().rposition(|c| !c.is_whitespace()).map_or(start, |index| index + 1);
+		sentences.push(start..end);
+	}
+	sentences
+}
+
+/// Sentences of a text with words in them.
+fn count_sentences(text: &str) -> usize {
+	let chars: Vec<char> = text.chars().collect();
+	sentences(&chars).into_iter()
+		.filter(|range| chars[range.clone()].iter().any(|c| c.is_alphanumeric()))
+		.count()
+}
+
+/// Rough English syllable count: groups of vowels, minus a silent final "e".
+fn count_syllables(word: &str) -> usize {
+	let word = word.to_lowercase();
+	let mut count = 0;
+	let mut previous_vowel = false;
+	for c in word.chars() {
+		let vowel = "aeiouy".contains(c);
+		if vowel && !previous_vowel {
+			count += 1;
+		}
+		previous_vowel = vowel;
+	}
+	if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+		count -= 1;
+	}
+	count.max(1)
+}
+
+impl TextStats {
+	pub(super) fn compute(text: &str, rules: CountingRules) -> Self {
+		let document = Document::new(text, rules);
+		let tokens = document.words();
+		let prose = document.prose_lines().map(|(_, line)| line).collect::<Vec<_>>().join("\n");
+		let characters = prose.chars().count();
+		let whitespaces = prose.chars().filter(|c| c.is_whitespace()).count();
+		let unique_words = tokens.iter()
+			.map(|token| token.to_lowercase())
+			.collect::<HashSet<_>>()
+			.len();
+		let syllables: Vec<usize> = tokens.iter().map(|token| count_syllables(token)).collect();
+		let sentences = count_sentences(&prose);
+		// Runs of lines with words in them, which blank lines and whatever
+		// isn't prose break.
+		let mut paragraphs = 0;
+		let mut last_line = None;
+		for (index, _) in document.prose_lines().filter(|(_, line)| line.chars().any(char::is_alphanumeric)) {
+			if !last_line.is_some_and(|last| last + 1 == index) {
+				paragraphs += 1;
+			}
+			last_line = Some(index);
+		}
+		Self {
+			words: tokens.len(),
+			characters,
+			characters_no_spaces: characters - whitespaces,
+			sentences,
+			paragraphs,
+			unique_words,
+			syllables: syllables.iter().sum(),
+			complex_words: syllables.iter().filter(|count| **count >= 3).count(),
+		}
+	}
+
+	fn words_per_sentence(&self) -> f64 {
+		self.words as f64 / self.sentences.max(1) as f64
+	}
+
+	pub(super) fn reading_minutes(&self) -> f64 {
+		self.words as f64 / READING_WPM
+	}
+
+	pub(super) fn speaking_minutes(&self) -> f64 {
+		self.words as f64 / SPEAKING_WPM
+	}
+
+	/// Flesch–Kincaid grade level.
+	pub(super) fn flesch_kincaid(&self) -> f64 {
+		if self.words == 0 {
+			return 0.0;
+		}
+		0.39 * self.words_per_sentence() + 11.8 * (self.syllables as f64 / self.words as f64) - 15.59
+	}
+
+	/// Gunning Fog index.
+	pub(super) fn gunning_fog(&self) -> f64 {
+		if self.words == 0 {
+			return 0.0;
+		}
+		0.4 * (self.words_per_sentence() + 100.0 * (self.complex_words as f64 / self.words as f64))
+	}
+}
+
+fn format_minutes(minutes: f64) -> String {
+	let total = minutes.round() as usize;
+	match (total / 60, total % 60) {
+		(0, 0) => i18n("Less than a minute"),
+		(0, minutes) => i18n_f("{minutes} min", &[("minutes", &minutes.to_string())]),
+		(hours, minutes) => i18n_f("{hours} h {minutes} min", &[("hours", &hours.to_string()), ("minutes", &minutes.to_string())]),
+	}
+}
+
+pub(super) fn metric_row(title: &str, value: String) -> adw::ActionRow {
+	let row = adw::ActionRow::builder()
+		.title(title)
+		.build();
+	let label = gtk::Label::builder()
+		.label(value)
+		.css_classes(["numeric", "dim-label"])
+		.selectable(true)
+		.build();
+	row.add_suffix(&label);
+	row
+}
+
+fn stats_page(stats: &TextStats, is_script: bool) -> adw::PreferencesPage {
+	let page = adw::PreferencesPage::new();
+
+	let counts = adw::PreferencesGroup::builder()
+		.title(i18n("Counts"))
+		.build();
+	counts.add(&metric_row(&i18n("Words"), stats.words.to_string()));
+	counts.add(&metric_row(&i18n("Unique Words"), stats.unique_words.to_string()));
+	counts.add(&metric_row(&i18n("Characters"), stats.characters.to_string()));
+	counts.add(&metric_row(&i18n("Characters Without Spaces"), stats.characters_no_spaces.to_string()));
+	counts.add(&metric_row(&i18n("Sentences"), stats.sentences.to_string()));
+	counts.add(&metric_row(&i18n("Paragraphs"), stats.paragraphs.to_string()));
+	page.add(&counts);
+
+	let time = adw::PreferencesGroup::builder()
+		.title(i18n("Time"))
+		.build();
+	time.add(&metric_row(&i18n("Reading Time"), format_minutes(stats.reading_minutes())));
+	if is_script {
+		time.add(&metric_row(&i18n("Speaking Time"), format_minutes(stats.speaking_minutes())));
+	}
+	page.add(&time);
+
+	let readability = adw::PreferencesGroup::builder()
+		.title(i18n("Readability"))
+		.build();
+	readability.add(&metric_row(&i18n("Average Sentence Length"), i18n_f("{count} words", &[("count", &format!("{:.1}", stats.words_per_sentence()))])));
+	readability.add(&metric_row(&i18n("Flesch–Kincaid Grade"), format!("{:.1}", stats.flesch_kincaid())));
+	readability.add(&metric_row(&i18n("Gunning Fog Index"), format!("{:.1}", stats.gunning_fog())));
+	page.add(&readability);
+
+	page
+}
+
+/// A page per scope, to be switched between in the Statistics dialog. Only
+/// scripts are given a speaking time.
+pub(super) fn stats_view_stack(scopes: &[(StatScope, TextStats)], is_script: bool) -> adw::ViewStack {
+	let stack = adw::ViewStack::new();
+	for (scope, stats) in scopes {
+		stack.add_titled_with_icon(&stats_page(stats, is_script), Some(scope.id()), &scope.label(), scope.icon_name());
+	}
+	stack
+}
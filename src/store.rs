@@ -0,0 +1,711 @@
+use std::{
+	collections::HashMap,
+	io::prelude::*,
+	path::{Path, PathBuf},
+	fs, fs::File,
+};
+
+use relm4::gtk::glib;
+
+use crate::{
+	i18n::i18n,
+	toc::{
+		SectionId, SectionStatus,
+	},
+};
+
+pub(super) fn writings_dir() -> PathBuf {
+	glib::user_data_dir().join("austeur").join("writings")
+}
+
+/// Writes `contents` to a file next to `path`, then moves it over `path`, so
+/// that a write cut short leaves the previous contents whole.
+pub(super) fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+	let mut temporary_name = path.file_name().unwrap_or_default().to_os_string();
+	temporary_name.push(".tmp");
+	let temporary = path.with_file_name(temporary_name);
+	let mut file = File::create(&temporary)?;
+	file.write_all(contents)?;
+	file.sync_all()?;
+	fs::rename(temporary, path)
+}
+
+/// Seconds since the Unix epoch, which modification times are kept in.
+pub(super) fn now() -> i64 {
+	glib::real_time() / 1_000_000
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct SectionMetadata {
+	pub(super) target: Option<usize>,
+	pub(super) status: SectionStatus,
+	pub(super) synopsis: Option<String>,
+}
+
+impl SectionMetadata {
+	fn from_json(value: &json::JsonValue) -> Self {
+		Self {
+			target: value["target"].as_usize(),
+			status: value["status"].as_str()
+				.and_then(SectionStatus::from_id)
+				.unwrap_or_default(),
+			synopsis: value["synopsis"].as_str().map(String::from),
+		}
+	}
+
+	fn to_json(&self) -> json::JsonValue {
+		let mut value = json::object! {
+			status: self.status.id(),
+		};
+		if let Some(target) = self.target {
+			value["target"] = target.into();
+		}
+		if let Some(synopsis) = &self.synopsis {
+			value["synopsis"] = synopsis.as_str().into();
+		}
+		value
+	}
+}
+
+/// What the writer aims for with a whole writing or project.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct Goal {
+	/// Words the writing should reach in total.
+	pub(super) target: Option<usize>,
+	/// Day by which the target should be reached, as `YYYY-MM-DD`.
+	pub(super) deadline: Option<String>,
+	/// Words to write every day.
+	pub(super) daily_target: Option<usize>,
+}
+
+impl Goal {
+	fn from_json(value: &json::JsonValue) -> Self {
+		Self {
+			target: value["target"].as_usize(),
+			deadline: value["deadline"].as_str().map(String::from),
+			daily_target: value["daily_target"].as_usize(),
+		}
+	}
+
+	fn to_json(&self) -> json::JsonValue {
+		let mut value = json::JsonValue::new_object();
+		if let Some(target) = self.target {
+			value["target"] = target.into();
+		}
+		if let Some(deadline) = &self.deadline {
+			value["deadline"] = deadline.as_str().into();
+		}
+		if let Some(daily_target) = self.daily_target {
+			value["daily_target"] = daily_target.into();
+		}
+		value
+	}
+}
+
+/// The outline saved along with a text, see `Outline::saved`.
+fn outline_from_json(value: &json::JsonValue) -> Vec<(SectionId, String)> {
+	value.members()
+		.filter_map(|section| Some((
+			SectionId::from_raw(section["id"].as_str()?.parse().ok()?),
+			section["name"].as_str()?.to_string(),
+		)))
+		.collect()
+}
+
+fn outline_to_json(outline: &[(SectionId, String)]) -> json::JsonValue {
+	let sections: Vec<json::JsonValue> = outline.iter()
+		.map(|(id, name)| json::object! {
+			// As a string, which JSON readers can hold whole.
+			id: id.to_raw().to_string(),
+			name: name.as_str(),
+		})
+		.collect();
+	sections.into()
+}
+
+#[derive(Debug, Clone, Default)]
+pub(super) struct WritingMetadata {
+	/// Keyed by the id of the section, which the outline of its text keeps.
+	pub(super) sections: HashMap<SectionId, SectionMetadata>,
+	pub(super) goal: Goal,
+	/// Language of the text, such as `fr`, which sets the rules of its
+	/// punctuation. Without one, that of the user is assumed.
+	pub(super) language: Option<String>,
+}
+
+impl WritingMetadata {
+	fn from_json(value: &json::JsonValue) -> Self {
+		let sections = value["sections"].entries()
+			.filter_map(|(id, section)| Some((SectionId::from_raw(id.parse().ok()?), SectionMetadata::from_json(section))))
+			.collect();
+		Self {
+			sections,
+			goal: Goal::from_json(&value["goal"]),
+			language: value["language"].as_str().map(String::from),
+		}
+	}
+
+	fn to_json(&self) -> json::JsonValue {
+		let mut sections = json::JsonValue::new_object();
+		for (id, section) in self.sections.iter() {
+			// Sections with default metadata are not worth persisting.
+			if *section == SectionMetadata::default() {
+				continue;
+			}
+			sections[id.to_raw().to_string().as_str()] = section.to_json();
+		}
+		let mut value = json::object! {
+			sections: sections,
+			goal: self.goal.to_json(),
+		};
+		if let Some(language) = &self.language {
+			value["language"] = language.as_str().into();
+		}
+		value
+	}
+
+	pub(super) fn section(&self, id: SectionId) -> SectionMetadata {
+		self.sections.get(&id).cloned().unwrap_or_default()
+	}
+
+	pub(super) fn section_mut(&mut self, id: SectionId) -> &mut SectionMetadata {
+		self.sections.entry(id).or_default()
+	}
+
+	/// Takes over the metadata of `other`, such as when merging writings.
+	/// Existing entries win over those of `other`.
+	pub(super) fn merge(&mut self, other: WritingMetadata) {
+		for (id, section) in other.sections {
+			self.sections.entry(id).or_insert(section);
+		}
+		if self.goal == Goal::default() {
+			self.goal = other.goal;
+		}
+		if self.language.is_none() {
+			self.language = other.language;
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Writing {
+	pub(super) id: String,
+	pub(super) title: String,
+	pub(super) text: String,
+	/// Ids and names of the sections of the text, which their metadata is
+	/// keyed by.
+	pub(super) outline: Vec<(SectionId, String)>,
+	pub(super) metadata: WritingMetadata,
+	/// When the writing was last edited, see `now`.
+	pub(super) modified: i64,
+}
+
+impl Writing {
+	pub(super) fn new() -> Self {
+		Self {
+			id: glib::uuid_string_random().to_string(),
+			title: String::new(),
+			text: String::new(),
+			outline: vec![],
+			metadata: WritingMetadata::default(),
+			modified: now(),
+		}
+	}
+
+	fn from_json(value: &json::JsonValue) -> Option<Self> {
+		Some(Self {
+			id: value["id"].as_str()?.to_string(),
+			title: value["title"].as_str().unwrap_or_default().to_string(),
+			text: value["text"].as_str().unwrap_or_default().to_string(),
+			outline: outline_from_json(&value["outline"]),
+			metadata: WritingMetadata::from_json(&value["metadata"]),
+			modified: value["modified"].as_i64().unwrap_or_default(),
+		})
+	}
+
+	fn to_json(&self) -> json::JsonValue {
+		json::object! {
+			id: self.id.as_str(),
+			title: self.title.as_str(),
+			text: self.text.as_str(),
+			outline: outline_to_json(&self.outline),
+			metadata: self.metadata.to_json(),
+			modified: self.modified,
+		}
+	}
+
+	/// Whether there is nothing in the writing worth keeping.
+	pub(super) fn is_empty(&self) -> bool {
+		self.title.trim().is_empty() && self.text.trim().is_empty()
+	}
+
+	fn load(path: PathBuf) -> Option<Self> {
+		let mut file = File::open(path).ok()?;
+		let mut contents = String::new();
+		file.read_to_string(&mut contents).ok()?;
+		let parsed = json::parse(contents.as_str()).ok()?;
+		Writing::from_json(&parsed)
+	}
+
+	pub(super) fn load_by_id(id: &str) -> Option<Self> {
+		Writing::load(writings_dir().join(format!("{}.json", id)))
+	}
+
+	/// Stored writings, the most recently edited first.
+	pub(super) fn load_all() -> Vec<Self> {
+		let dir = writings_dir();
+		if !dir.is_dir() {
+			return vec![];
+		};
+		let entries = match fs::read_dir(dir) {
+			Ok(val) => val,
+			Err(..) => {
+				return vec![];
+			},
+		};
+		let mut writings: Vec<Self> = entries
+			.filter_map(|res| res.ok())
+			.map(|entry| entry.path())
+			.filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+			.filter_map(Writing::load)
+			.collect();
+		writings.sort_by_key(|writing| std::cmp::Reverse(writing.modified));
+		writings
+	}
+
+	pub(super) fn delete(id: &str) -> std::io::Result<()> {
+		fs::remove_file(writings_dir().join(format!("{}.json", id)))
+	}
+
+	pub(super) fn save(&self) -> std::io::Result<()> {
+		let dir = writings_dir();
+		fs::create_dir_all(&dir)?;
+		write_atomically(&dir.join(format!("{}.json", self.id)), json::stringify_pretty(self.to_json(), 4).as_bytes())
+	}
+}
+
+pub(super) fn projects_dir() -> PathBuf {
+	glib::user_data_dir().join("austeur").join("projects")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum DocumentKind {
+	Folder,
+	Chapter,
+	Scene,
+	Note,
+	Research,
+}
+
+impl DocumentKind {
+	pub(super) const ALL: [DocumentKind; 5] = [
+		DocumentKind::Folder,
+		DocumentKind::Chapter,
+		DocumentKind::Scene,
+		DocumentKind::Note,
+		DocumentKind::Research,
+	];
+
+	fn id(&self) -> &'static str {
+		match self {
+			DocumentKind::Folder => "folder",
+			DocumentKind::Chapter => "chapter",
+			DocumentKind::Scene => "scene",
+			DocumentKind::Note => "note",
+			DocumentKind::Research => "research",
+		}
+	}
+
+	fn from_id(id: &str) -> Option<Self> {
+		DocumentKind::ALL.into_iter().find(|kind| kind.id() == id)
+	}
+
+	pub(super) fn label(&self) -> String {
+		match self {
+			DocumentKind::Folder => i18n("Folder"),
+			DocumentKind::Chapter => i18n("Chapter"),
+			DocumentKind::Scene => i18n("Scene"),
+			DocumentKind::Note => i18n("Note"),
+			DocumentKind::Research => i18n("Research"),
+		}
+	}
+
+	pub(super) fn icon_name(&self) -> &'static str {
+		match self {
+			DocumentKind::Folder => "folder-symbolic",
+			DocumentKind::Chapter => "text-justify-left-symbolic",
+			DocumentKind::Scene => "paper-symbolic",
+			DocumentKind::Note => "lightbulb-symbolic",
+			DocumentKind::Research => "loupe-symbolic",
+		}
+	}
+
+	/// Whether documents of this kind are part of the compiled manuscript.
+	pub(super) fn is_manuscript(&self) -> bool {
+		matches!(self, DocumentKind::Folder | DocumentKind::Chapter | DocumentKind::Scene)
+	}
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct BinderItem {
+	pub(super) id: String,
+	pub(super) title: String,
+	pub(super) kind: DocumentKind,
+	pub(super) text: String,
+	/// Like `Writing::outline`. The metadata of the sections is the project's.
+	pub(super) outline: Vec<(SectionId, String)>,
+	pub(super) children: Vec<BinderItem>,
+}
+
+impl BinderItem {
+	pub(super) fn new(kind: DocumentKind, title: String, text: String) -> Self {
+		Self {
+			id: glib::uuid_string_random().to_string(),
+			title,
+			kind,
+			text,
+			outline: vec![],
+			children: vec![],
+		}
+	}
+
+	fn from_json(value: &json::JsonValue) -> Option<Self> {
+		Some(Self {
+			id: value["id"].as_str()?.to_string(),
+			title: value["title"].as_str().unwrap_or_default().to_string(),
+			kind: value["kind"].as_str().and_then(DocumentKind::from_id).unwrap_or(DocumentKind::Chapter),
+			text: value["text"].as_str().unwrap_or_default().to_string(),
+			outline: outline_from_json(&value["outline"]),
+			children: value["children"].members().filter_map(BinderItem::from_json).collect(),
+		})
+	}
+
+	fn to_json(&self) -> json::JsonValue {
+		let children: Vec<json::JsonValue> = self.children.iter().map(BinderItem::to_json).collect();
+		json::object! {
+			id: self.id.as_str(),
+			title: self.title.as_str(),
+			kind: self.kind.id(),
+			text: self.text.as_str(),
+			outline: outline_to_json(&self.outline),
+			children: children,
+		}
+	}
+}
+
+/// Where to move a document in the binder, relative to where it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum BinderMove {
+	Up,
+	Down,
+	/// Become the last child of the previous sibling.
+	Indent,
+	/// Become the next sibling of the parent.
+	Outdent,
+}
+
+/// Finds the list of siblings that holds the item `id`, and its position there.
+fn locate_mut<'a>(items: &'a mut Vec<BinderItem>, id: &str) -> Option<(&'a mut Vec<BinderItem>, usize)> {
+	if let Some(index) = items.iter().position(|item| item.id == id) {
+		return Some((items, index));
+	}
+	for item in items.iter_mut() {
+		if let Some(found) = locate_mut(&mut item.children, id) {
+			return Some(found);
+		}
+	}
+	None
+}
+
+/// Like `locate_mut`, but for the parent of the item `id`.
+fn locate_parent_mut<'a>(items: &'a mut Vec<BinderItem>, id: &str) -> Option<(&'a mut Vec<BinderItem>, usize)> {
+	if let Some(index) = items.iter().position(|item| item.children.iter().any(|child| child.id == id)) {
+		return Some((items, index));
+	}
+	for item in items.iter_mut() {
+		if let Some(found) = locate_parent_mut(&mut item.children, id) {
+			return Some(found);
+		}
+	}
+	None
+}
+
+fn flatten<'a>(items: &'a [BinderItem], depth: usize, out: &mut Vec<(usize, &'a BinderItem)>) {
+	for item in items {
+		out.push((depth, item));
+		flatten(&item.children, depth + 1, out);
+	}
+}
+
+/// A writing made of many documents arranged in a tree, the binder.
+#[derive(Debug, Clone)]
+pub(super) struct Project {
+	pub(super) id: String,
+	pub(super) title: String,
+	pub(super) items: Vec<BinderItem>,
+	pub(super) metadata: WritingMetadata,
+	/// What the project was started as, if it came from a template.
+	pub(super) template: Option<ProjectTemplate>,
+}
+
+impl Project {
+	pub(super) fn new(title: String) -> Self {
+		Self {
+			id: glib::uuid_string_random().to_string(),
+			title,
+			items: vec![],
+			metadata: WritingMetadata::default(),
+			template: None,
+		}
+	}
+
+	fn from_json(value: &json::JsonValue) -> Option<Self> {
+		Some(Self {
+			id: value["id"].as_str()?.to_string(),
+			title: value["title"].as_str().unwrap_or_default().to_string(),
+			items: value["items"].members().filter_map(BinderItem::from_json).collect(),
+			metadata: WritingMetadata::from_json(&value["metadata"]),
+			template: value["template"].as_str().and_then(ProjectTemplate::from_id),
+		})
+	}
+
+	fn to_json(&self) -> json::JsonValue {
+		let items: Vec<json::JsonValue> = self.items.iter().map(BinderItem::to_json).collect();
+		json::object! {
+			id: self.id.as_str(),
+			title: self.title.as_str(),
+			items: items,
+			metadata: self.metadata.to_json(),
+			template: self.template.map(|template| template.id()),
+		}
+	}
+
+	fn load(path: PathBuf) -> Option<Self> {
+		let mut file = File::open(path).ok()?;
+		let mut contents = String::new();
+		file.read_to_string(&mut contents).ok()?;
+		let parsed = json::parse(contents.as_str()).ok()?;
+		Project::from_json(&parsed)
+	}
+
+	pub(super) fn load_by_id(id: &str) -> Option<Self> {
+		Project::load(projects_dir().join(format!("{}.json", id)))
+	}
+
+	pub(super) fn load_all() -> Vec<Self> {
+		let entries = match fs::read_dir(projects_dir()) {
+			Ok(val) => val,
+			Err(..) => {
+				return vec![];
+			},
+		};
+		entries
+			.filter_map(|res| res.ok())
+			.map(|entry| entry.path())
+			.filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+			.filter_map(Project::load)
+			.collect()
+	}
+
+	pub(super) fn save(&self) -> std::io::Result<()> {
+		let dir = projects_dir();
+		fs::create_dir_all(&dir)?;
+		write_atomically(&dir.join(format!("{}.json", self.id)), json::stringify_pretty(self.to_json(), 4).as_bytes())
+	}
+
+	/// Documents in binder order, with their depth in the tree.
+	pub(super) fn flatten(&self) -> Vec<(usize, &BinderItem)> {
+		let mut out = Vec::new();
+		flatten(&self.items, 0, &mut out);
+		out
+	}
+
+	pub(super) fn find(&self, id: &str) -> Option<&BinderItem> {
+		self.flatten().into_iter().map(|(_, item)| item).find(|item| item.id == id)
+	}
+
+	pub(super) fn find_mut(&mut self, id: &str) -> Option<&mut BinderItem> {
+		let (siblings, index) = locate_mut(&mut self.items, id)?;
+		siblings.get_mut(index)
+	}
+
+	/// Adds `item` right after the document `after`, or at the end of the
+	/// binder if there is none.
+	pub(super) fn insert_after(&mut self, after: Option<&str>, item: BinderItem) {
+		match after.and_then(|id| locate_mut(&mut self.items, id)) {
+			Some((siblings, index)) => siblings.insert(index + 1, item),
+			None => self.items.push(item),
+		}
+	}
+
+	pub(super) fn remove(&mut self, id: &str) -> Option<BinderItem> {
+		let (siblings, index) = locate_mut(&mut self.items, id)?;
+		Some(siblings.remove(index))
+	}
+
+	pub(super) fn move_item(&mut self, id: &str, direction: BinderMove) {
+		match direction {
+			BinderMove::Up => {
+				if let Some((siblings, index)) = locate_mut(&mut self.items, id) {
+					if index > 0 {
+						siblings.swap(index - 1, index);
+					}
+				}
+			},
+			BinderMove::Down => {
+				if let Some((siblings, index)) = locate_mut(&mut self.items, id) {
+					if index + 1 < siblings.len() {
+						siblings.swap(index, index + 1);
+					}
+				}
+			},
+			BinderMove::Indent => {
+				if let Some((siblings, index)) = locate_mut(&mut self.items, id) {
+					if index > 0 {
+						let item = siblings.remove(index);
+						siblings[index - 1].children.push(item);
+					}
+				}
+			},
+			BinderMove::Outdent => {
+				if let Some((siblings, parent_index)) = locate_parent_mut(&mut self.items, id) {
+					let parent = &mut siblings[parent_index];
+					if let Some(index) = parent.children.iter().position(|child| child.id == id) {
+						let item = parent.children.remove(index);
+						siblings.insert(parent_index + 1, item);
+					}
+				}
+			},
+		}
+	}
+
+	/// The manuscript documents in binder order. Notes and research, and
+	/// everything nested in them, are left out.
+	pub(super) fn manuscript(&self) -> Vec<&BinderItem> {
+		fn manuscript_items<'a>(items: &'a [BinderItem], out: &mut Vec<&'a BinderItem>) {
+			for item in items.iter().filter(|item| item.kind.is_manuscript()) {
+				out.push(item);
+				manuscript_items(&item.children, out);
+			}
+		}
+		let mut out = Vec::new();
+		manuscript_items(&self.items, &mut out);
+		out
+	}
+
+	/// Concatenates the manuscript documents in binder order.
+	pub(super) fn compile(&self) -> String {
+		let parts: Vec<&str> = self.manuscript().into_iter()
+			.filter(|item| !item.text.trim().is_empty())
+			.map(|item| item.text.trim_end())
+			.collect();
+		parts.join("\n\n") + "\n"
+	}
+}
+
+/// Starting structures for a new project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ProjectTemplate {
+	Novel,
+	ShortStory,
+	Screenplay,
+	StagePlay,
+	Essay,
+}
+
+impl ProjectTemplate {
+	pub(super) const ALL: [ProjectTemplate; 5] = [
+		ProjectTemplate::Novel,
+		ProjectTemplate::ShortStory,
+		ProjectTemplate::Screenplay,
+		ProjectTemplate::StagePlay,
+		ProjectTemplate::Essay,
+	];
+
+	pub(super) fn id(&self) -> &'static str {
+		match self {
+			ProjectTemplate::Novel => "novel",
+			ProjectTemplate::ShortStory => "short-story",
+			ProjectTemplate::Screenplay => "screenplay",
+			ProjectTemplate::StagePlay => "stage-play",
+			ProjectTemplate::Essay => "essay",
+		}
+	}
+
+	pub(super) fn from_id(id: &str) -> Option<Self> {
+		ProjectTemplate::ALL.into_iter().find(|template| template.id() == id)
+	}
+
+	/// Whether the project is meant to be performed, and so is timed as spoken.
+	pub(super) fn is_script(&self) -> bool {
+		matches!(self, ProjectTemplate::Screenplay | ProjectTemplate::StagePlay)
+	}
+
+	pub(super) fn label(&self) -> String {
+		match self {
+			ProjectTemplate::Novel => i18n("Novel"),
+			ProjectTemplate::ShortStory => i18n("Short Story"),
+			ProjectTemplate::Screenplay => i18n("Screenplay"),
+			ProjectTemplate::StagePlay => i18n("Stage Play"),
+			ProjectTemplate::Essay => i18n("Essay"),
+		}
+	}
+
+	fn item(kind: DocumentKind, title: String, text: &str, children: Vec<BinderItem>) -> BinderItem {
+		let mut item = BinderItem::new(kind, title, text.to_string());
+		item.children = children;
+		item
+	}
+
+	fn manuscript(&self) -> Vec<BinderItem> {
+		use DocumentKind::*;
+		let item = ProjectTemplate::item;
+		match self {
+			ProjectTemplate::Novel => vec![
+				item(Folder, i18n("Manuscript"), "", vec![
+					item(Chapter, i18n("Chapter 1"), "", vec![
+						item(Scene, i18n("Scene 1"), "", vec![]),
+					]),
+				]),
+				item(Note, i18n("Characters"), "", vec![]),
+				item(Note, i18n("Places"), "", vec![]),
+			],
+			ProjectTemplate::ShortStory => vec![
+				item(Chapter, i18n("Draft"), "", vec![]),
+			],
+			ProjectTemplate::Screenplay => vec![
+				item(Folder, i18n("Act One"), "", vec![
+					item(Scene, i18n("Opening"), "INT. LOCATION - DAY\n\n", vec![]),
+				]),
+				item(Folder, i18n("Act Two"), "", vec![]),
+				item(Folder, i18n("Act Three"), "", vec![]),
+			],
+			ProjectTemplate::StagePlay => vec![
+				item(Folder, i18n("Act I"), "", vec![
+					item(Scene, i18n("Scene 1"), "", vec![]),
+				]),
+				item(Folder, i18n("Act II"), "", vec![]),
+				item(Note, i18n("Dramatis Personae"), "", vec![]),
+			],
+			ProjectTemplate::Essay => vec![
+				item(Chapter, i18n("Introduction"), "", vec![]),
+				item(Chapter, i18n("Body"), "", vec![]),
+				item(Chapter, i18n("Conclusion"), "", vec![]),
+			],
+		}
+	}
+
+	/// Builds a project from the template, keeping `premise` as a note along
+	/// with its metadata.
+	pub(super) fn build(&self, title: String, premise: Writing) -> Project {
+		let mut project = Project::new(title);
+		project.items = self.manuscript();
+		let mut note = BinderItem::new(DocumentKind::Note, i18n("Premise"), premise.text);
+		note.outline = premise.outline;
+		project.items.push(note);
+		project.items.push(BinderItem::new(DocumentKind::Research, i18n("Research"), String::new()));
+		project.metadata = premise.metadata;
+		project.template = Some(*self);
+		project
+	}
+}
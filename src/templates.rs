@@ -0,0 +1,149 @@
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+use relm4::gtk::glib;
+
+use crate::{
+	i18n::i18n,
+};
+
+pub(super) fn templates_dir() -> PathBuf {
+	glib::user_data_dir().join("austeur").join("templates")
+}
+
+const CURSOR: &str = "{{cursor}}";
+
+/// Markdown to start a new writing from. The placeholders `{{title}}`,
+/// `{{date}}` and `{{cursor}}` are filled in when the template is used.
+#[derive(Debug, Clone)]
+pub(super) struct Template {
+	pub(super) name: String,
+	pub(super) body: String,
+	pub(super) is_builtin: bool,
+}
+
+impl Template {
+	fn builtin(name: String, body: &str) -> Self {
+		Self {
+			name,
+			body: body.to_string(),
+			is_builtin: true,
+		}
+	}
+
+	fn builtins() -> Vec<Self> {
+		vec![
+			Template::builtin(i18n("Hero's Journey"), "\
+# {{title}}
+
+## The Ordinary World
+{{cursor}}
+
+## The Call to Adventure
+
+## Refusal of the Call
+
+## Meeting the Mentor
+
+## Crossing the Threshold
+
+## Tests, Allies, Enemies
+
+## Approach to the Inmost Cave
+
+## The Ordeal
+
+## Reward
+
+## The Road Back
+
+## Resurrection
+
+## Return with the Elixir
+"),
+			Template::builtin(i18n("Three-Act Structure"), "\
+# {{title}}
+
+## Act One: Setup
+{{cursor}}
+
+## Act Two: Confrontation
+
+## Act Three: Resolution
+"),
+			Template::builtin(i18n("Journal Entry"), "\
+# {{date}}
+
+{{cursor}}
+"),
+			Template::builtin(i18n("Poem"), "\
+# {{title}}
+
+{{cursor}}
+
+*{{date}}*
+"),
+		]
+	}
+
+	fn load(path: &Path) -> Option<Self> {
+		Some(Self {
+			name: path.file_stem()?.to_string_lossy().to_string(),
+			body: fs::read_to_string(path).ok()?,
+			is_builtin: false,
+		})
+	}
+
+	/// Built-in templates, then those the user has added to the data dir.
+	pub(super) fn load_all() -> Vec<Self> {
+		let mut templates = Template::builtins();
+		if let Ok(entries) = fs::read_dir(templates_dir()) {
+			let mut user: Vec<Self> = entries
+				.filter_map(|res| res.ok())
+				.map(|entry| entry.path())
+				.filter(|path| path.extension().is_some_and(|ext| ext == "md"))
+				.filter_map(|path| Template::load(&path))
+				.collect();
+			user.sort_by(|a, b| a.name.cmp(&b.name));
+			templates.append(&mut user);
+		}
+		templates
+	}
+
+	/// Copies a Markdown file into the templates dir.
+	pub(super) fn import(path: &Path) -> std::io::Result<Self> {
+		let dir = templates_dir();
+		fs::create_dir_all(&dir)?;
+		let file_name = path.file_name()
+			.ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a file"))?;
+		let destination = dir.join(file_name).with_extension("md");
+		fs::copy(path, &destination)?;
+		Template::load(&destination)
+			.ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "not a template"))
+	}
+
+	pub(super) fn export(&self, path: &Path) -> std::io::Result<()> {
+		fs::write(path, &self.body)
+	}
+
+	/// Fills in the placeholders, returning the text and the character offset
+	/// where the cursor should be placed, if the template says so.
+	pub(super) fn expand(&self, title: &str) -> (String, Option<usize>) {
+		let date = glib::DateTime::now_local()
+			.and_then(|now| now.format("%x"))
+			.map(|date| date.to_string())
+			.unwrap_or_default();
+		let text = self.body
+			.replace("{{title}}", title)
+			.replace("{{date}}", &date);
+		match text.find(CURSOR) {
+			Some(index) => {
+				let offset = text[..index].chars().count();
+				(text.replacen(CURSOR, "", 1), Some(offset))
+			},
+			None => (text, None),
+		}
+	}
+}
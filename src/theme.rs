@@ -0,0 +1,392 @@
+use std::{
+	cell::{Cell, RefCell},
+	path::PathBuf,
+	rc::Rc,
+};
+
+use relm4::{
+	adw, adw::prelude::*,
+	gtk, gtk::prelude::*, gtk::{gdk, gio, glib},
+};
+
+use sourceview5::prelude::*;
+
+use crate::i18n::i18n;
+
+pub(super) const DEFAULT_THEME: &str = "austeur-default";
+/// Id of the scheme written by the scheme editor.
+const CUSTOM_THEME: &str = "austeur-custom";
+const DARK_SUFFIX: &str = "-dark";
+
+const SAMPLE: &str = "# Chapter One\n\n\"Come in,\" she said. The door was *already* open.\n\n<!-- Check the dates. -->\nSee [[Notes]] or [the map](map.png).\n\n* * *\n";
+
+/// Where the style schemes of the user are looked up, next to the bundled ones.
+pub(super) fn user_styles_dir() -> PathBuf {
+	glib::user_data_dir().join("austeur").join("styles")
+}
+
+/// Id of the scheme of `theme` for the light or dark style. A theme without
+/// a dark variant is used as it is, and an unknown one falls back to the
+/// default theme.
+pub(super) fn scheme_id(manager: &sourceview5::StyleSchemeManager, theme: &str, is_dark: bool) -> String {
+	let variant = format!("{}{}", theme, DARK_SUFFIX);
+	if is_dark && manager.scheme(&variant).is_some() {
+		variant
+	} else if manager.scheme(theme).is_some() {
+		theme.to_string()
+	} else if is_dark {
+		format!("{}{}", DEFAULT_THEME, DARK_SUFFIX)
+	} else {
+		DEFAULT_THEME.to_string()
+	}
+}
+
+/// Style sheet overriding the accent color of the application, if the user
+/// picked one. Otherwise that of the light or dark style sheet is kept.
+pub(super) fn accent_css(settings: &gio::Settings) -> String {
+	match gdk::RGBA::parse(settings.string("accent-color").as_str()) {
+		Ok(color) => format!("@define-color accent_color {};", hex(&color)),
+		Err(_) => String::new(),
+	}
+}
+
+/// Themes to choose from, as their ids and names. Dark variants are picked
+/// along with the theme they belong to.
+fn themes(manager: &sourceview5::StyleSchemeManager) -> Vec<(String, String)> {
+	let ids: Vec<String> = manager.scheme_ids().iter().map(|id| id.to_string()).collect();
+	let mut themes: Vec<(String, String)> = ids.iter()
+		.filter(|id| !id.strip_suffix(DARK_SUFFIX).is_some_and(|theme| ids.iter().any(|other| other == theme)))
+		.filter_map(|id| Some((id.clone(), manager.scheme(id)?.name().to_string())))
+		.collect();
+	themes.sort_by(|(a, _), (b, _)| (a != DEFAULT_THEME).cmp(&(b != DEFAULT_THEME)).then(a.cmp(b)));
+	themes
+}
+
+fn hex(color: &gdk::RGBA) -> String {
+	let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+	format!("#{:02x}{:02x}{:02x}", channel(color.red()), channel(color.green()), channel(color.blue()))
+}
+
+/// A color of the scheme editor, and the style of the scheme it goes to.
+struct Swatch {
+	title: String,
+	style: &'static str,
+	/// Whether it is the background of the style rather than its foreground.
+	is_background: bool,
+	button: gtk::ColorDialogButton,
+}
+
+impl Swatch {
+	fn new(title: String, style: &'static str, is_background: bool, scheme: Option<&sourceview5::StyleScheme>, fallback: &str) -> Self {
+		let property = if is_background { "background" } else { "foreground" };
+		let color = scheme
+			.and_then(|scheme| scheme.style(style))
+			.and_then(|style| style.property::<Option<String>>(property))
+			.or_else(|| scheme.and_then(|scheme| scheme.style("text")).and_then(|style| style.property::<Option<String>>("foreground")))
+			.and_then(|color| gdk::RGBA::parse(color.as_str()).ok())
+			.unwrap_or_else(|| gdk::RGBA::parse(fallback).unwrap());
+		let dialog = gtk::ColorDialog::new();
+		dialog.set_with_alpha(false);
+		let button = gtk::ColorDialogButton::new(Some(dialog));
+		button.set_valign(gtk::Align::Center);
+		button.set_rgba(&color);
+		Self { title, style, is_background, button }
+	}
+
+	fn row(&self) -> adw::ActionRow {
+		let row = adw::ActionRow::builder()
+			.title(self.title.as_str())
+			.activatable_widget(&self.button)
+			.build();
+		row.add_suffix(&self.button);
+		row
+	}
+}
+
+/// Id of the variant of the custom scheme for the light or dark style.
+fn custom_scheme_id(is_dark: bool) -> String {
+	if is_dark {
+		format!("{}{}", CUSTOM_THEME, DARK_SUFFIX)
+	} else {
+		CUSTOM_THEME.to_string()
+	}
+}
+
+/// The scheme made of the colors of the editor, over the default theme.
+fn custom_scheme(swatches: &[Swatch], is_dark: bool) -> String {
+	let (parent, variant) = if is_dark {
+		(format!("{}{}", DEFAULT_THEME, DARK_SUFFIX), "dark")
+	} else {
+		(DEFAULT_THEME.to_string(), "light")
+	};
+	let mut styles: Vec<(&str, Vec<String>)> = vec![];
+	for swatch in swatches {
+		let attribute = format!("{}=\"{}\"", if swatch.is_background { "background" } else { "foreground" }, hex(&swatch.button.rgba()));
+		match styles.iter_mut().find(|(style, _)| *style == swatch.style) {
+			Some((_, attributes)) => attributes.push(attribute),
+			None => styles.push((swatch.style, vec![attribute])),
+		}
+	}
+	let styles: String = styles.into_iter()
+		.map(|(style, attributes)| {
+			let extra = match style {
+				"austeur-markdown:heading" => " bold=\"true\"",
+				"austeur-markdown:comment" => " italic=\"true\"",
+				_ => "",
+			};
+			format!("  <style name=\"{}\" {}{}/>\n", style, attributes.join(" "), extra)
+		})
+		.collect();
+	format!(
+		"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<style-scheme id=\"{}\" name=\"{}\" version=\"1.0\" parent-scheme=\"{}\">\n  <metadata>\n    <property name=\"variant\">{}</property>\n  </metadata>\n{}</style-scheme>\n",
+		custom_scheme_id(is_dark),
+		glib::markup_escape_text(&i18n("Custom")),
+		parent,
+		variant,
+		styles,
+	)
+}
+
+/// Writes the variant of the custom scheme for the style in use, along with
+/// the other variant if there is none yet, which is left as its default
+/// theme until edited in its own style.
+fn write_custom_scheme(swatches: &[Swatch], is_dark: bool) -> std::io::Result<()> {
+	let directory = user_styles_dir();
+	std::fs::create_dir_all(&directory)?;
+	std::fs::write(directory.join(format!("{}.xml", custom_scheme_id(is_dark))), custom_scheme(swatches, is_dark))?;
+	let other = directory.join(format!("{}.xml", custom_scheme_id(!is_dark)));
+	if !other.exists() {
+		std::fs::write(other, custom_scheme(&[], !is_dark))?;
+	}
+	Ok(())
+}
+
+/// Editor of the colors of the text, starting from the scheme in use. Every
+/// change is written to a scheme of the user and picked as the theme, so the
+/// sample and the text of the window follow along.
+fn scheme_editor(settings: &gio::Settings, manager: &sourceview5::StyleSchemeManager, on_change: Rc<dyn Fn()>) -> adw::Dialog {
+	let is_dark = adw::StyleManager::default().is_dark();
+	let current = manager.scheme(&scheme_id(manager, &settings.string("theme"), is_dark));
+	let (background, foreground) = if is_dark { ("#1e1e1e", "#ffffff") } else { ("#fafafa", "#000000") };
+	let swatches = Rc::new(vec![
+		Swatch::new(i18n("Background"), "text", true, current.as_ref(), background),
+		Swatch::new(i18n("Text"), "text", false, current.as_ref(), foreground),
+		Swatch::new(i18n("Headings"), "austeur-markdown:heading", false, current.as_ref(), foreground),
+		Swatch::new(i18n("Markup"), "austeur-markdown:markup", false, current.as_ref(), foreground),
+		Swatch::new(i18n("Dialogue"), "austeur-markdown:dialogue", false, current.as_ref(), foreground),
+		Swatch::new(i18n("Comments"), "austeur-markdown:comment", false, current.as_ref(), foreground),
+		Swatch::new(i18n("Links"), "austeur-markdown:link", false, current.as_ref(), foreground),
+	]);
+
+	let sample_buffer = sourceview5::Buffer::new(None);
+	sample_buffer.set_language(sourceview5::LanguageManager::default().language("austeur-markdown").as_ref());
+	sample_buffer.set_highlight_matching_brackets(false);
+	sample_buffer.set_text(SAMPLE);
+	sample_buffer.set_style_scheme(current.as_ref());
+	let sample = sourceview5::View::with_buffer(&sample_buffer);
+	sample.set_editable(false);
+	sample.set_cursor_visible(false);
+	sample.set_wrap_mode(gtk::WrapMode::WordChar);
+	sample.set_left_margin(12);
+	sample.set_right_margin(12);
+	sample.set_top_margin(12);
+	sample.set_bottom_margin(12);
+	sample.add_css_class("editor");
+	sample.add_css_class("card");
+	sample.set_overflow(gtk::Overflow::Hidden);
+
+	let group = adw::PreferencesGroup::builder()
+		.title(i18n("Colors"))
+		.description(if is_dark { i18n("For the dark style") } else { i18n("For the light style") })
+		.build();
+	for swatch in swatches.iter() {
+		group.add(&swatch.row());
+		let swatches = swatches.clone();
+		let settings = settings.clone();
+		let manager = manager.clone();
+		let sample_buffer = sample_buffer.clone();
+		let on_change = on_change.clone();
+		swatch.button.connect_rgba_notify(move |_| {
+			if let Err(err) = write_custom_scheme(&swatches, is_dark) {
+				eprintln!("Could not save colors: {}", err);
+				return;
+			}
+			manager.force_rescan();
+			sample_buffer.set_style_scheme(manager.scheme(&custom_scheme_id(is_dark)).as_ref());
+			if settings.string("theme") != CUSTOM_THEME {
+				if let Err(err) = settings.set_string("theme", CUSTOM_THEME) {
+					eprintln!("Could not change theme: {}", err);
+				}
+			}
+			// The theme may have been the custom one already, which the
+			// text of the window holds on to as it was before the rescan.
+			on_change();
+		});
+	}
+
+	let content = gtk::Box::new(gtk::Orientation::Vertical, 18);
+	content.set_margin_top(12);
+	content.set_margin_bottom(24);
+	content.set_margin_start(24);
+	content.set_margin_end(24);
+	content.append(&sample);
+	content.append(&group);
+	let scroller = gtk::ScrolledWindow::builder()
+		.hscrollbar_policy(gtk::PolicyType::Never)
+		.propagate_natural_height(true)
+		.child(&content)
+		.build();
+
+	let toolbar_view = adw::ToolbarView::new();
+	toolbar_view.add_top_bar(&adw::HeaderBar::new());
+	toolbar_view.set_content(Some(&scroller));
+
+	adw::Dialog::builder()
+		.title(i18n("Edit Colors"))
+		.content_width(480)
+		.child(&toolbar_view)
+		.build()
+}
+
+/// Fills `combo` with the themes, selecting that of the settings.
+fn fill_themes(combo: &adw::ComboRow, ids: &RefCell<Vec<String>>, manager: &sourceview5::StyleSchemeManager, settings: &gio::Settings) {
+	let themes = themes(manager);
+	let names = gtk::StringList::new(&themes.iter().map(|(_, name)| name.as_str()).collect::<Vec<_>>());
+	ids.replace(themes.into_iter().map(|(id, _)| id).collect());
+	combo.set_model(Some(&names));
+	let theme = settings.string("theme");
+	let selected = ids.borrow().iter().position(|id| *id == theme.as_str()).unwrap_or(0);
+	combo.set_selected(selected as u32);
+}
+
+fn theme_row(settings: &gio::Settings, manager: &sourceview5::StyleSchemeManager) -> (adw::ComboRow, Rc<dyn Fn()>) {
+	let combo = adw::ComboRow::builder()
+		.title(i18n("Theme"))
+		.build();
+	let ids = Rc::new(RefCell::new(Vec::<String>::new()));
+	// Filling in the themes changes the selection, which is not a choice.
+	let is_filling = Rc::new(Cell::new(true));
+	fill_themes(&combo, &ids, manager, settings);
+	is_filling.set(false);
+
+	{
+		let ids = ids.clone();
+		let is_filling = is_filling.clone();
+		let settings = settings.clone();
+		combo.connect_selected_notify(move |combo| {
+			if is_filling.get() {
+				return;
+			}
+			let Some(id) = ids.borrow().get(combo.selected() as usize).cloned() else {
+				return;
+			};
+			if settings.string("theme") != id.as_str() {
+				if let Err(err) = settings.set_string("theme", &id) {
+					eprintln!("Could not change theme: {}", err);
+				}
+			}
+		});
+	}
+
+	let refill: Rc<dyn Fn()> = {
+		let combo = combo.clone();
+		let settings = settings.clone();
+		let manager = manager.clone();
+		Rc::new(move || {
+			is_filling.set(true);
+			fill_themes(&combo, &ids, &manager, &settings);
+			is_filling.set(false);
+		})
+	};
+	(combo, refill)
+}
+
+fn accent_row(settings: &gio::Settings) -> adw::ActionRow {
+	let row = adw::ActionRow::builder()
+		.title(i18n("Accent Color"))
+		.build();
+	let dialog = gtk::ColorDialog::new();
+	dialog.set_with_alpha(false);
+	let button = gtk::ColorDialogButton::new(Some(dialog));
+	button.set_valign(gtk::Align::Center);
+	// The accent colors of the light and dark style sheets.
+	let theme_accent = if adw::StyleManager::default().is_dark() { "#d00019" } else { "#bd242c" };
+	let accent = gdk::RGBA::parse(settings.string("accent-color").as_str())
+		.unwrap_or_else(|_| gdk::RGBA::parse(theme_accent).unwrap());
+	button.set_rgba(&accent);
+	{
+		let settings = settings.clone();
+		button.connect_rgba_notify(move |button| {
+			let color = hex(&button.rgba());
+			if settings.string("accent-color") != color.as_str() {
+				if let Err(err) = settings.set_string("accent-color", &color) {
+					eprintln!("Could not change accent color: {}", err);
+				}
+			}
+		});
+	}
+
+	let reset = gtk::Button::builder()
+		.icon_name("edit-undo-symbolic")
+		.tooltip_text(i18n("Use the Color of the Theme"))
+		.valign(gtk::Align::Center)
+		.build();
+	reset.add_css_class("flat");
+	{
+		let settings = settings.clone();
+		reset.connect_clicked(move |_| {
+			settings.reset("accent-color");
+		});
+	}
+	row.add_suffix(&reset);
+	row.add_suffix(&button);
+	row
+}
+
+/// Preferences of the look of the application: the theme of the text, its
+/// colors and the accent color. `on_change` is called whenever the colors of
+/// a theme are edited.
+pub(super) fn appearance_page(
+	settings: &gio::Settings,
+	manager: &sourceview5::StyleSchemeManager,
+	on_change: impl Fn() + 'static,
+) -> adw::PreferencesPage {
+	let page = adw::PreferencesPage::builder()
+		.title(i18n("Appearance"))
+		.icon_name("applications-graphics-symbolic")
+		.build();
+
+	let theme = adw::PreferencesGroup::builder()
+		.title(i18n("Theme"))
+		.description(i18n("Style schemes saved in the styles folder of the application data are listed too"))
+		.build();
+	let (theme_row, refill) = theme_row(settings, manager);
+	theme.add(&theme_row);
+
+	let edit_row = adw::ActionRow::builder()
+		.title(i18n("Edit Colors…"))
+		.activatable(true)
+		.build();
+	edit_row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+	{
+		let settings = settings.clone();
+		let manager = manager.clone();
+		let on_change: Rc<dyn Fn()> = Rc::new(move || {
+			refill();
+			on_change();
+		});
+		edit_row.connect_activated(move |row| {
+			scheme_editor(&settings, &manager, on_change.clone()).present(row);
+		});
+	}
+	theme.add(&edit_row);
+	page.add(&theme);
+
+	let accent = adw::PreferencesGroup::builder()
+		.title(i18n("Interface"))
+		.build();
+	accent.add(&accent_row(settings));
+	page.add(&accent);
+
+	page
+}
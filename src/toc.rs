@@ -2,48 +2,385 @@ use crate::{
 	app::{
 		AppMsg
 	},
+	i18n::i18n,
+	store::{
+		SectionMetadata
+	},
 };
 
 use relm4::{
 	prelude::*,
 	factory, factory::{ FactoryComponent },
-	gtk, gtk::prelude::*
+	gtk, gtk::prelude::*, gtk::glib,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum SectionStatus {
+	#[default]
+	ToDo,
+	Draft,
+	Revised,
+	Final,
+}
+
+impl SectionStatus {
+	pub(super) const ALL: [SectionStatus; 4] = [
+		SectionStatus::ToDo,
+		SectionStatus::Draft,
+		SectionStatus::Revised,
+		SectionStatus::Final,
+	];
+
+	/// Stable identifier used when persisting to the writing's metadata.
+	pub(super) fn id(&self) -> &'static str {
+		match self {
+			SectionStatus::ToDo => "todo",
+			SectionStatus::Draft => "draft",
+			SectionStatus::Revised => "revised",
+			SectionStatus::Final => "final",
+		}
+	}
+
+	pub(super) fn from_id(id: &str) -> Option<Self> {
+		SectionStatus::ALL.into_iter().find(|status| status.id() == id)
+	}
+
+	pub(super) fn label(&self) -> String {
+		match self {
+			SectionStatus::ToDo => i18n("To Do"),
+			SectionStatus::Draft => i18n("Draft"),
+			SectionStatus::Revised => i18n("Revised"),
+			SectionStatus::Final => i18n("Final"),
+		}
+	}
+
+	pub(super) fn css_class(&self) -> &'static str {
+		match self {
+			SectionStatus::ToDo => "status-todo",
+			SectionStatus::Draft => "status-draft",
+			SectionStatus::Revised => "status-revised",
+			SectionStatus::Final => "status-final",
+		}
+	}
+
+	fn position(&self) -> u32 {
+		SectionStatus::ALL.iter().position(|status| status == self).unwrap_or_default() as u32
+	}
+}
+
+pub(super) fn draw_progress_ring(progress: f64) -> impl Fn(&gtk::DrawingArea, &gtk::cairo::Context, i32, i32) {
+	move |area, cr, width, height| {
+		let color = area.color();
+		let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+		let radius = cx.min(cy) - 2.0;
+		cr.set_line_width(3.0);
+
+		cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, 0.15);
+		cr.arc(cx, cy, radius, 0.0, 2.0 * std::f64::consts::PI);
+		let _ = cr.stroke();
+
+		if progress > 0.0 {
+			let start = -std::f64::consts::FRAC_PI_2;
+			cr.set_source_rgba(color.red() as f64, color.green() as f64, color.blue() as f64, color.alpha() as f64);
+			cr.arc(cx, cy, radius, start, start + 2.0 * std::f64::consts::PI * progress.min(1.0));
+			let _ = cr.stroke();
+		}
+	}
+}
+
+/// Identity of a section that survives edits to its heading and to its
+/// siblings, and is saved along with the text. Ids are drawn at random, so
+/// that those of the documents of a project never clash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct SectionId(u64);
+
+impl SectionId {
+	fn random() -> Self {
+		Self((glib::random_int() as u64) << 32 | glib::random_int() as u64)
+	}
+
+	/// Number standing for the id, such as in the payload of a drag.
+	pub(super) fn to_raw(&self) -> u64 {
+		self.0
+	}
+
+	pub(super) fn from_raw(raw: u64) -> Self {
+		Self(raw)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct OutlineEntry {
+	pub(super) id: SectionId,
+	pub(super) name: String,
+	pub(super) word_count: usize,
+}
+
+/// A change to apply to the outline. Applying them in order to the previous
+/// list of sections yields the new one.
+#[derive(Debug)]
+pub(super) enum OutlineChange {
+	Insert(usize, OutlineEntry),
+	Remove(usize, SectionId),
+	Update {
+		index: usize,
+		entry: OutlineEntry,
+	},
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Outline {
+	entries: Vec<OutlineEntry>,
+}
+
+impl Outline {
+	/// The outline saved along with a text, as a list of `(id, name)`. Word
+	/// counts come with the next update.
+	pub(super) fn restore(saved: Vec<(SectionId, String)>) -> Self {
+		Self {
+			entries: saved.into_iter()
+				.map(|(id, name)| OutlineEntry { id, name, word_count: 0 })
+				.collect(),
+		}
+	}
+
+	/// The ids and names of the sections, to save along with the text.
+	pub(super) fn saved(&self) -> Vec<(SectionId, String)> {
+		self.entries.iter()
+			.map(|entry| (entry.id, entry.name.clone()))
+			.collect()
+	}
+
+	pub(super) fn entries(&self) -> &[OutlineEntry] {
+		&self.entries
+	}
+
+	pub(super) fn find(&self, id: SectionId) -> Option<(usize, &OutlineEntry)> {
+		self.entries.iter().enumerate().find(|(_, entry)| entry.id == id)
+	}
+
+	/// Replaces the outline with `headings`, a list of `(name, word count)`.
+	///
+	/// Edits usually touch a single spot of the document, so the unchanged
+	/// headings at both ends are kept. A single heading between them replaced
+	/// by another is that heading retyped. Otherwise, only the headings found
+	/// unchanged in between, such as those of moved sections, are kept.
+	pub(super) fn update(&mut self, headings: Vec<(String, usize)>) -> Vec<OutlineChange> {
+		let old = std::mem::take(&mut self.entries);
+		let prefix = old.iter().zip(headings.iter())
+			.take_while(|(entry, (name, _))| entry.name == *name)
+			.count();
+		let suffix = old[prefix..].iter().rev().zip(headings[prefix..].iter().rev())
+			.take_while(|(entry, (name, _))| entry.name == *name)
+			.count();
+		let old_middle = old.len() - prefix - suffix;
+		let new_middle = headings.len() - prefix - suffix;
+		let is_retyped = old_middle == 1 && new_middle == 1;
+
+		let mut unmatched: Vec<&OutlineEntry> = if is_retyped {
+			vec![]
+		} else {
+			old[prefix..prefix + old_middle].iter().collect()
+		};
+		let mut changes: Vec<OutlineChange> = unmatched.iter()
+			.map(|entry| OutlineChange::Remove(prefix, entry.id))
+			.collect();
+		let mut entries = Vec::with_capacity(headings.len());
+		for (index, (name, word_count)) in headings.into_iter().enumerate() {
+			// Position of the section that this heading continues, if any.
+			let previous = if index < prefix || (is_retyped && index == prefix) {
+				Some(&old[index])
+			} else if index >= prefix + new_middle {
+				Some(&old[index - new_middle + old_middle])
+			} else {
+				None
+			};
+			let entry = match previous {
+				Some(previous) => {
+					let entry = OutlineEntry { id: previous.id, name, word_count };
+					if entry != *previous {
+						changes.push(OutlineChange::Update { index, entry: entry.clone() });
+					}
+					entry
+				},
+				None => {
+					let id = unmatched.iter().position(|entry| entry.name == name)
+						.map(|position| unmatched.remove(position).id)
+						.unwrap_or_else(SectionId::random);
+					let entry = OutlineEntry { id, name, word_count };
+					changes.push(OutlineChange::Insert(index, entry.clone()));
+					entry
+				},
+			};
+			entries.push(entry);
+		}
+		self.entries = entries;
+		changes
+	}
+}
+
 #[derive(Debug)]
 pub(super) struct Section {
-	index: u8,
+	id: SectionId,
 	name: String,
+	word_count: usize,
+	metadata: SectionMetadata,
+	visible: bool,
+}
+
+impl Section {
+	fn progress(&self) -> f64 {
+		match self.metadata.target {
+			Some(target) if target > 0 => self.word_count as f64 / target as f64,
+			_ => 0.0,
+		}
+	}
+
+	fn word_count_label(&self) -> String {
+		match self.metadata.target {
+			Some(target) => format!("{} / {}", self.word_count, target),
+			None => format!("{}", self.word_count),
+		}
+	}
+
+	pub(super) fn update(&mut self, entry: OutlineEntry) {
+		self.name = entry.name;
+		self.word_count = entry.word_count;
+	}
+
+	pub(super) fn set_metadata(&mut self, metadata: SectionMetadata) {
+		self.metadata = metadata;
+	}
+
+	pub(super) fn set_visible(&mut self, visible: bool) {
+		self.visible = visible;
+	}
+
+	pub(super) fn visible(&self) -> bool {
+		self.visible
+	}
 }
 
 #[derive(Debug)]
 pub(super) enum SectionMessage {
-	
+
 }
 
 #[derive(Debug)]
 pub(super) enum SectionOutput {
-	ScrollToHere,
+	ScrollToHere(SectionId),
+	ChangeStatus(SectionId, SectionStatus),
+	ChangeTarget(SectionId, Option<usize>),
+	SplitIntoWriting(SectionId),
 }
 
 #[factory(pub)]
 impl FactoryComponent for Section {
-	type Init = (u8, String);
+	type Init = (OutlineEntry, SectionMetadata, bool);
 	type Input = SectionMessage;
 	type Output = SectionOutput;
 	type CommandOutput = ();
 	type ParentWidget = gtk::Box;
 
 	view! {
-		root = gtk::ToggleButton {
-			set_label: &self.name,
+		root = gtk::Box {
+			set_spacing: 6,
+			#[watch]
+			set_visible: self.visible,
+
+			gtk::ToggleButton {
+				set_hexpand: true,
+				#[watch]
+				set_label: &self.name,
+
+				connect_clicked[sender, id = self.id] => move |_| {
+					sender.output(SectionOutput::ScrollToHere(id)).unwrap();
+				},
+			},
+
+			gtk::Label {
+				add_css_class: "dim-label",
+				add_css_class: "numeric",
+				#[watch]
+				set_label: &self.word_count_label(),
+			},
+
+			gtk::DrawingArea {
+				set_content_width: 16,
+				set_content_height: 16,
+				set_valign: gtk::Align::Center,
+				#[watch]
+				set_visible: self.metadata.target.is_some(),
+				#[watch]
+				set_draw_func: draw_progress_ring(self.progress()),
+			},
+
+			gtk::MenuButton {
+				#[watch]
+				set_css_classes: &["flat", "status", self.metadata.status.css_class()],
+				#[watch]
+				set_label: &self.metadata.status.label(),
+				set_tooltip_text: Some(&i18n("Status & Goal")),
+
+				#[wrap(Some)]
+				set_popover = &gtk::Popover {
+					gtk::Box {
+						set_orientation: gtk::Orientation::Vertical,
+						set_spacing: 6,
+
+						gtk::DropDown::from_strings(&SectionStatus::ALL.map(|status| status.label()).iter().map(String::as_str).collect::<Vec<_>>()) {
+							set_selected: self.metadata.status.position(),
+
+							connect_selected_notify[sender, id = self.id] => move |dropdown| {
+								if let Some(status) = SectionStatus::ALL.get(dropdown.selected() as usize) {
+									sender.output(SectionOutput::ChangeStatus(id, *status)).unwrap();
+								}
+							},
+						},
+
+						gtk::Box {
+							set_spacing: 6,
+
+							gtk::Label {
+								set_label: &i18n("Target"),
+								set_hexpand: true,
+								set_xalign: 0.0,
+							},
+
+							gtk::SpinButton::with_range(0.0, 1_000_000.0, 100.0) {
+								set_tooltip_text: Some(&i18n("Word target, 0 for none")),
+								set_value: self.metadata.target.unwrap_or_default() as f64,
+
+								connect_value_changed[sender, id = self.id] => move |spin| {
+									let target = spin.value() as usize;
+									sender.output(SectionOutput::ChangeTarget(id, Some(target).filter(|x| *x > 0))).unwrap();
+								},
+							},
+						},
+
+						gtk::Button {
+							add_css_class: "flat",
+							set_label: &i18n("Split into New Writing"),
+							set_tooltip_text: Some(&i18n("Move this section and its subsections into a writing of its own")),
+
+							connect_clicked[sender, id = self.id] => move |_| {
+								sender.output(SectionOutput::SplitIntoWriting(id)).unwrap();
+							},
+						},
+					},
+				},
+			},
 		}
 	}
 
 	fn init_model(value: Self::Init, _index: &DynamicIndex, _sender: FactorySender<Self>) -> Self {
+		let (entry, metadata, visible) = value;
 		Self {
-			index: value.0,
-			name: value.1
+			id: entry.id,
+			name: entry.name,
+			word_count: entry.word_count,
+			metadata,
+			visible,
 		}
 	}
 }
@@ -0,0 +1,119 @@
+use std::{
+	cell::{Cell, RefCell},
+	rc::Rc,
+};
+
+use relm4::{
+	gtk, gtk::glib, gtk::prelude::*,
+};
+
+/// Share of the remaining distance covered on each frame of the animation.
+const EASING: f64 = 0.25;
+/// Below this distance, in pixels, the animation settles.
+const SETTLED: f64 = 0.5;
+
+/// Keeps the line of the cursor at a fixed height of the scrolled window.
+///
+/// The cursor is placed from its on-screen location, so wrapped lines and
+/// text being composed with an input method are followed as they are shown.
+/// The target is worked out again on every frame of the animation, so the
+/// layout of the text view catching up after an edit is never a jolt.
+pub(super) struct Typewriter {
+	text_view: sourceview5::View,
+	scroller: gtk::ScrolledWindow,
+	is_enabled: Cell<bool>,
+	/// Height at which the cursor is kept, from 0 at the top to 1 at the bottom.
+	position: Cell<f64>,
+	tick: RefCell<Option<gtk::TickCallbackId>>,
+}
+
+impl Typewriter {
+	pub(super) fn new(text_view: &sourceview5::View, scroller: &gtk::ScrolledWindow) -> Rc<Self> {
+		let typewriter = Rc::new(Self {
+			text_view: text_view.clone(),
+			scroller: scroller.clone(),
+			is_enabled: Cell::new(false),
+			position: Cell::new(0.5),
+			tick: RefCell::new(None),
+		});
+
+		let weak = Rc::downgrade(&typewriter);
+		scroller.vadjustment().connect_page_size_notify(move |_| {
+			if let Some(typewriter) = weak.upgrade() {
+				typewriter.update_margins();
+			}
+		});
+		let weak = Rc::downgrade(&typewriter);
+		text_view.connect_preedit_changed(move |_, _| {
+			if let Some(typewriter) = weak.upgrade() {
+				typewriter.recenter();
+			}
+		});
+		typewriter
+	}
+
+	pub(super) fn set_enabled(self: &Rc<Self>, is_enabled: bool, position: f64) {
+		self.is_enabled.set(is_enabled);
+		self.position.set(position.clamp(0.0, 1.0));
+		self.update_margins();
+		if is_enabled {
+			self.recenter();
+		} else if let Some(tick) = self.tick.take() {
+			tick.remove();
+		}
+	}
+
+	/// Room above and below the text, so its first and last lines can be
+	/// brought up to the cursor height too.
+	fn update_margins(&self) {
+		let (top, bottom) = if self.is_enabled.get() {
+			let page = self.scroller.vadjustment().page_size();
+			let position = self.position.get();
+			((page * position) as i32, (page * (1.0 - position)) as i32)
+		} else {
+			(0, 0)
+		};
+		self.text_view.set_top_margin(top);
+		self.text_view.set_bottom_margin(bottom);
+	}
+
+	/// Scroll value that puts the cursor at its height.
+	fn target(&self) -> Option<f64> {
+		let (cursor, _) = self.text_view.cursor_locations(None);
+		let (_, y) = self.text_view.buffer_to_window_coords(gtk::TextWindowType::Widget, cursor.x(), cursor.y());
+		let origin = gtk::graphene::Point::new(0.0, 0.0);
+		let offset = self.text_view.compute_point(&self.scroller, &origin)?;
+		let adjustment = self.scroller.vadjustment();
+		// Where the middle of the cursor lies in the scrolled content.
+		let cursor_y = adjustment.value() + offset.y() as f64 + y as f64 + cursor.height() as f64 / 2.0;
+		let target = cursor_y - adjustment.page_size() * self.position.get();
+		Some(target.clamp(adjustment.lower(), (adjustment.upper() - adjustment.page_size()).max(adjustment.lower())))
+	}
+
+	/// Starts scrolling towards the cursor, unless already on the way.
+	pub(super) fn recenter(self: &Rc<Self>) {
+		if !self.is_enabled.get() || self.tick.borrow().is_some() {
+			return;
+		}
+		let weak = Rc::downgrade(self);
+		let tick = self.text_view.add_tick_callback(move |_, _| {
+			let Some(typewriter) = weak.upgrade() else {
+				return glib::ControlFlow::Break;
+			};
+			let adjustment = typewriter.scroller.vadjustment();
+			let Some(target) = typewriter.target() else {
+				typewriter.tick.replace(None);
+				return glib::ControlFlow::Break;
+			};
+			let distance = target - adjustment.value();
+			if distance.abs() < SETTLED {
+				adjustment.set_value(target);
+				typewriter.tick.replace(None);
+				return glib::ControlFlow::Break;
+			}
+			adjustment.set_value(adjustment.value() + distance * EASING);
+			glib::ControlFlow::Continue
+		});
+		self.tick.replace(Some(tick));
+	}
+}
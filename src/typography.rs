@@ -0,0 +1,156 @@
+use relm4::{
+	adw, adw::prelude::*,
+	gtk, gtk::prelude::*, gtk::{gio, pango},
+};
+
+use crate::i18n::i18n;
+
+/// Bounds of the zoom of the text, and the step of each Ctrl+/Ctrl−.
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+const ZOOM_STEP: f64 = 0.1;
+
+/// How the text of the editor is set, as chosen in the preferences.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Typography {
+	pub(super) font_family: String,
+	/// In points, before zooming.
+	pub(super) font_size: f64,
+	pub(super) line_height: f64,
+	/// Space below each paragraph, in pixels.
+	pub(super) paragraph_spacing: i32,
+	pub(super) first_line_indent: i32,
+	/// Largest width of the column of text, in pixels.
+	pub(super) text_width: i32,
+	pub(super) zoom: f64,
+}
+
+impl Typography {
+	pub(super) fn load(settings: &gio::Settings) -> Self {
+		Self {
+			font_family: settings.string("font-family").to_string(),
+			font_size: settings.double("font-size"),
+			line_height: settings.double("line-height"),
+			paragraph_spacing: settings.double("paragraph-spacing") as i32,
+			first_line_indent: settings.double("first-line-indent") as i32,
+			text_width: settings.double("text-width") as i32,
+			zoom: settings.double("zoom"),
+		}
+	}
+
+	/// Font family as a CSS value, quoted, falling back to monospace.
+	fn css_font_family(&self) -> String {
+		let family = self.font_family.replace(['"', '\\', ';', '}'], "");
+		format!("\"{}\", monospace", family)
+	}
+
+	fn scaled_font_size(&self) -> f64 {
+		self.font_size * self.zoom
+	}
+
+	/// Style of the text view of the editor, which has the `editor` class.
+	pub(super) fn css(&self) -> String {
+		format!(
+			"textview.editor, textview.editor > text {{ font-family: {}; font-size: {:.1}pt; line-height: {:.2}; }}",
+			self.css_font_family(),
+			self.scaled_font_size(),
+			self.line_height,
+		)
+	}
+
+	/// The same typography for the HTML of the preview.
+	pub(super) fn preview_css(&self) -> String {
+		format!(
+			"body {{ max-width: {}px; font-family: {}; font-size: {:.1}pt; line-height: {:.2}; }} p {{ margin: 0 0 {}px; text-indent: {}px; }}",
+			self.text_width,
+			self.css_font_family(),
+			self.scaled_font_size(),
+			self.line_height,
+			self.paragraph_spacing,
+			self.first_line_indent,
+		)
+	}
+}
+
+/// Zooms the text in or out by `steps`, or back to its size with none.
+pub(super) fn zoom(settings: &gio::Settings, steps: Option<i32>) {
+	let zoom = match steps {
+		Some(steps) => (settings.double("zoom") + steps as f64 * ZOOM_STEP).clamp(MIN_ZOOM, MAX_ZOOM),
+		None => 1.0,
+	};
+	if let Err(err) = settings.set_double("zoom", zoom) {
+		eprintln!("Could not zoom: {}", err);
+	}
+}
+
+fn spin_row(settings: &gio::Settings, key: &str, title: &str, range: (f64, f64, f64), digits: u32) -> adw::SpinRow {
+	let (min, max, step) = range;
+	let row = adw::SpinRow::with_range(min, max, step);
+	row.set_title(title);
+	row.set_digits(digits);
+	settings.bind(key, &row, "value").build();
+	row
+}
+
+fn font_row(settings: &gio::Settings) -> adw::ActionRow {
+	let row = adw::ActionRow::builder()
+		.title(i18n("Font"))
+		.build();
+	let button = gtk::FontDialogButton::new(Some(gtk::FontDialog::new()));
+	button.set_level(gtk::FontLevel::Family);
+	button.set_valign(gtk::Align::Center);
+	button.set_font_desc(&pango::FontDescription::from_string(&settings.string("font-family")));
+	let settings = settings.clone();
+	button.connect_font_desc_notify(move |button| {
+		let family = button.font_desc().and_then(|desc| desc.family());
+		if let Some(family) = family {
+			if let Err(err) = settings.set_string("font-family", &family) {
+				eprintln!("Could not change font: {}", err);
+			}
+		}
+	});
+	row.add_suffix(&button);
+	row
+}
+
+/// Preferences of the editor. Every row is bound to its setting, and the
+/// editor follows the settings as they change.
+pub(super) fn preferences_dialog(settings: &gio::Settings) -> adw::PreferencesDialog {
+	let dialog = adw::PreferencesDialog::new();
+	let page = adw::PreferencesPage::builder()
+		.title(i18n("Editor"))
+		.icon_name("text-justify-left-symbolic")
+		.build();
+
+	let text = adw::PreferencesGroup::builder()
+		.title(i18n("Text"))
+		.build();
+	text.add(&font_row(settings));
+	text.add(&spin_row(settings, "font-size", &i18n("Font Size"), (6.0, 72.0, 1.0), 0));
+	text.add(&spin_row(settings, "line-height", &i18n("Line Height"), (1.0, 3.0, 0.1), 1));
+	page.add(&text);
+
+	let layout = adw::PreferencesGroup::builder()
+		.title(i18n("Layout"))
+		.build();
+	layout.add(&spin_row(settings, "paragraph-spacing", &i18n("Paragraph Spacing"), (0.0, 64.0, 2.0), 0));
+	layout.add(&spin_row(settings, "first-line-indent", &i18n("First-Line Indent"), (0.0, 128.0, 4.0), 0));
+	layout.add(&spin_row(settings, "text-width", &i18n("Text Width"), (320.0, 2000.0, 20.0), 0));
+	page.add(&layout);
+
+	let typewriter = adw::PreferencesGroup::builder()
+		.title(i18n("Typewriter Scrolling"))
+		.build();
+	let switch_row = adw::SwitchRow::builder()
+		.title(i18n("Keep the Line Being Edited in Place"))
+		.build();
+	settings.bind("typewriter-scrolling", &switch_row, "active").build();
+	typewriter.add(&switch_row);
+	let position_row = spin_row(settings, "typewriter-position", &i18n("Height of the Line"), (0.1, 0.9, 0.05), 2);
+	position_row.set_subtitle(&i18n("From 0.1 at the top to 0.9 at the bottom"));
+	typewriter.add(&position_row);
+	page.add(&typewriter);
+
+	dialog.add(&page);
+	dialog
+}
@@ -0,0 +1,288 @@
+/// How words are told apart. Where scripts disagree on what a word is, these
+/// pick the convention, following what word processors commonly do.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct CountingRules {
+	/// Count each Chinese or Japanese character as a word, rather than each
+	/// run of them. Vietnamese is always counted by syllable, as it is spaced.
+	pub(super) cjk_characters_as_words: bool,
+	/// Count "well-known" as one word rather than two.
+	pub(super) hyphenated_as_one: bool,
+	pub(super) count_numbers: bool,
+	/// Count a link or an email address as one word rather than none.
+	pub(super) count_urls: bool,
+	/// Leave out Markdown that is not prose: link targets, HTML tags and
+	/// comments, and the front matter.
+	pub(super) exclude_markup: bool,
+}
+
+impl Default for CountingRules {
+	fn default() -> Self {
+		Self {
+			cjk_characters_as_words: true,
+			hyphenated_as_one: true,
+			count_numbers: true,
+			count_urls: true,
+			exclude_markup: true,
+		}
+	}
+}
+
+/// Han ideographs, and the kana of Japanese. Hangul is left out as Korean is
+/// spaced between words.
+fn is_cjk(c: char) -> bool {
+	matches!(c,
+		'\u{3040}'..='\u{30FF}'
+		| '\u{31F0}'..='\u{31FF}'
+		| '\u{3400}'..='\u{4DBF}'
+		| '\u{4E00}'..='\u{9FFF}'
+		| '\u{F900}'..='\u{FAFF}'
+		| '\u{FF66}'..='\u{FF9D}'
+		| '\u{20000}'..='\u{2FA1F}'
+	)
+}
+
+/// Combining diacritics, such as those of decomposed Vietnamese, belong to
+/// the letter before them.
+fn is_combining_mark(c: char) -> bool {
+	matches!(c,
+		'\u{0300}'..='\u{036F}'
+		| '\u{1AB0}'..='\u{1AFF}'
+		| '\u{1DC0}'..='\u{1DFF}'
+		| '\u{20D0}'..='\u{20FF}'
+		| '\u{FE20}'..='\u{FE2F}'
+	)
+}
+
+fn is_word_char(c: char) -> bool {
+	!is_cjk(c) && (c.is_alphanumeric() || is_combining_mark(c))
+}
+
+fn is_url(chunk: &str) -> bool {
+	let chunk = chunk.trim_matches(|c: char| matches!(c, '<' | '>' | '(' | ')' | '[' | ']' | '"' | '\'' | ',' | '.' | ';' | ':'));
+	const PREFIXES: [&str; 5] = ["http://", "https://", "ftp://", "mailto:", "www."];
+	if PREFIXES.iter().any(|prefix| chunk.starts_with(prefix)) {
+		return true;
+	}
+	// An email address.
+	chunk.split_once('@').is_some_and(|(user, domain)| {
+		!user.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
+	})
+}
+
+/// Whether the character at `index` joins the word characters on its sides
+/// into one word.
+fn joins(chars: &[char], index: usize, rules: &CountingRules) -> bool {
+	let (Some(before), Some(after)) = (index.checked_sub(1).and_then(|i| chars.get(i)), chars.get(index + 1)) else {
+		return false;
+	};
+	if !is_word_char(*before) || !is_word_char(*after) {
+		return false;
+	}
+	match chars[index] {
+		'\'' | '’' => true,
+		'-' | '‐' => rules.hyphenated_as_one,
+		// Separators in numbers, like 3.14 or 1,000.
+		'.' | ',' => before.is_ascii_digit() && after.is_ascii_digit(),
+		_ => false,
+	}
+}
+
+/// Calls `word` with each word of `chunk`, a piece of text without spaces.
+fn for_each_word<'a>(chunk: &'a str, rules: &CountingRules, word: &mut impl FnMut(&'a str)) {
+	if is_url(chunk) {
+		if rules.count_urls {
+			word(chunk);
+		}
+		return;
+	}
+	let chars: Vec<char> = chunk.chars().collect();
+	let offsets: Vec<usize> = chunk.char_indices()
+		.map(|(offset, _)| offset)
+		.chain(std::iter::once(chunk.len()))
+		.collect();
+	let mut i = 0;
+	while i < chars.len() {
+		if is_cjk(chars[i]) {
+			let start = i;
+			while i < chars.len() && is_cjk(chars[i]) {
+				i += 1;
+			}
+			if rules.cjk_characters_as_words {
+				(start..i).for_each(|c| word(&chunk[offsets[c]..offsets[c + 1]]));
+			} else {
+				word(&chunk[offsets[start]..offsets[i]]);
+			}
+		} else if is_word_char(chars[i]) {
+			let start = i;
+			let mut is_number = true;
+			while i < chars.len() && (is_word_char(chars[i]) || joins(&chars, i, rules)) {
+				if chars[i].is_alphabetic() {
+					is_number = false;
+				}
+				i += 1;
+			}
+			if !is_number || rules.count_numbers {
+				word(&chunk[offsets[start]..offsets[i]]);
+			}
+		} else {
+			i += 1;
+		}
+	}
+}
+
+/// Removes the Markdown of a line that is not prose, keeping the text of links.
+/// Comments spanning several lines are left to the caller.
+fn strip_markup(line: &str) -> String {
+	lazy_static::lazy_static! {
+		static ref COMMENT: regex::Regex = regex::Regex::new(r"<!--.*?-->").unwrap();
+		static ref IMAGE: regex::Regex = regex::Regex::new(r"!\[[^\]]*\]\([^)]*\)").unwrap();
+		static ref LINK: regex::Regex = regex::Regex::new(r"\[([^\]]*)\]\([^)]*\)").unwrap();
+		static ref FOOTNOTE: regex::Regex = regex::Regex::new(r"\[\^[^\]]*\]").unwrap();
+		static ref TAG: regex::Regex = regex::Regex::new(r"</?[A-Za-z][^>]*>").unwrap();
+		static ref ORDERED_LIST: regex::Regex = regex::Regex::new(r"^\s*\d+[.)]\s").unwrap();
+	}
+	let line = COMMENT.replace_all(line, " ");
+	let line = IMAGE.replace_all(&line, " ");
+	let line = LINK.replace_all(&line, "$1");
+	let line = FOOTNOTE.replace_all(&line, "");
+	let line = TAG.replace_all(&line, " ");
+	let line = ORDERED_LIST.replace(&line, "");
+	line.into_owned()
+}
+
+/// Calls `word` with each word of a piece of text that has no multi-line
+/// markup, such as a line.
+fn for_each_word_of(text: &str, rules: &CountingRules, mut word: impl FnMut(&str)) {
+	let stripped;
+	let text = if rules.exclude_markup {
+		stripped = strip_markup(text);
+		stripped.as_str()
+	} else {
+		text
+	};
+	for chunk in text.split_whitespace() {
+		for_each_word(chunk, rules, &mut word);
+	}
+}
+
+/// Words in a piece of text that has no multi-line markup, such as a line.
+pub(super) fn count_words(text: &str, rules: &CountingRules) -> usize {
+	let mut count = 0;
+	for_each_word_of(text, rules, |_| count += 1);
+	count
+}
+
+/// The words that `count_words` counts, for the analyses that look into them.
+pub(super) fn words(text: &str, rules: &CountingRules) -> Vec<String> {
+	let mut words = Vec::new();
+	for_each_word_of(text, rules, |word| words.push(word.to_string()));
+	words
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		CountingRules, count_words,
+	};
+	use crate::document::{
+		Document, split_lines,
+	};
+
+	fn count(text: &str) -> usize {
+		Document::new(text, CountingRules::default()).word_count()
+	}
+
+	fn count_with(text: &str, rules: CountingRules) -> usize {
+		Document::new(text, rules).word_count()
+	}
+
+	#[test]
+	fn counts_cjk_by_character_or_by_run() {
+		let by_run = CountingRules { cjk_characters_as_words: false, ..CountingRules::default() };
+		assert_eq!(count("我喜欢写作"), 5);
+		assert_eq!(count_with("我喜欢写作", by_run), 1);
+		assert_eq!(count("これはペンです"), 7);
+		assert_eq!(count("I love 東京"), 4);
+		assert_eq!(count_with("I love 東京", by_run), 3);
+	}
+
+	#[test]
+	fn counts_vietnamese_by_syllable() {
+		assert_eq!(count("Tôi yêu tiếng Việt"), 4);
+		// Decomposed, with combining diacritics.
+		assert_eq!(count("Tie\u{0302}\u{0301}ng Vie\u{0323}\u{0302}t"), 2);
+	}
+
+	#[test]
+	fn counts_hyphenated_words() {
+		let apart = CountingRules { hyphenated_as_one: false, ..CountingRules::default() };
+		assert_eq!(count("a well-known fact"), 3);
+		assert_eq!(count_with("a well-known fact", apart), 4);
+		assert_eq!(count("it isn't — she said"), 4);
+	}
+
+	#[test]
+	fn counts_numbers() {
+		let text = "It cost 3.14 or 1,000 dollars in 2024";
+		assert_eq!(count(text), 8);
+		assert_eq!(count_with(text, CountingRules { count_numbers: false, ..CountingRules::default() }), 5);
+		// The marker of an ordered list is not a number of the text.
+		assert_eq!(count("1. First item"), 2);
+	}
+
+	#[test]
+	fn counts_urls_and_email_addresses() {
+		let text = "See https://example.com/a-b or mail me@example.org today";
+		assert_eq!(count(text), 6);
+		assert_eq!(count_with(text, CountingRules { count_urls: false, ..CountingRules::default() }), 4);
+	}
+
+	#[test]
+	fn leaves_out_front_matter() {
+		let text = "---\ntitle: My Book\n---\nOne two three.";
+		assert_eq!(count(text), 3);
+		assert_eq!(count_with(text, CountingRules { exclude_markup: false, ..CountingRules::default() }), 6);
+		// Only the first line can open front matter.
+		assert_eq!(count("One\n---\ntwo\n---"), 2);
+		// And only with a `---`, once a fence closes it.
+		assert_eq!(count("...\ntitle: x\n---\nOne"), 3);
+		assert_eq!(count("---\nNot front matter yet"), 4);
+		// Counting markup leaves front matter out of the prose all the same.
+		let document = Document::new(text, CountingRules { exclude_markup: false, ..CountingRules::default() });
+		assert_eq!(document.prose_lines().map(|(index, _)| index).collect::<Vec<_>>(), [3]);
+	}
+
+	#[test]
+	fn leaves_out_comments() {
+		assert_eq!(count("Before <!-- hidden words --> after"), 2);
+		assert_eq!(count("One\n<!-- a\nb c -->\nTwo"), 2);
+		assert_eq!(count("One <!-- a\nb --> two"), 2);
+	}
+
+	#[test]
+	fn counts_headings_and_links_as_prose() {
+		assert_eq!(count("# Chapter One\nIt began."), 4);
+		assert_eq!(count("See [the map](https://example.com/map.png) and ![a map](map.png)."), 4);
+		assert_eq!(count_words("**Bold** and _italic_", &CountingRules::default()), 3);
+	}
+
+	#[test]
+	fn finds_headings_outside_code() {
+		let text = "# One\nI like C# and Rust\n####### Seven\n```\n# Not a heading\n```\n## Two";
+		let sections: Vec<String> = Document::new(text, CountingRules::default()).sections().into_iter().map(|(name, _)| name).collect();
+		assert_eq!(sections, ["# One", "## Two"]);
+	}
+
+	#[test]
+	fn lists_the_words_it_counts() {
+		let document = Document::new("---\ntitle: Draft\n---\nDon't <!-- hidden\nwords --> stop, 3.14 東京", CountingRules::default());
+		assert_eq!(document.words(), ["Don't", "stop", "3.14", "東", "京"]);
+		assert_eq!(document.words().len(), document.word_count());
+	}
+
+	#[test]
+	fn splits_lines_at_every_break() {
+		assert_eq!(split_lines("a\nb\r\nc\rd\u{2029}e\r\r\n"), ["a", "b", "c", "d", "e", "", ""]);
+		assert_eq!(Document::new("# One\r\nText\r\n# Two", CountingRules::default()).line_count(), 3);
+	}
+}
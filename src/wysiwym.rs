@@ -0,0 +1,166 @@
+use std::ops::Range;
+
+use relm4::{
+	gtk, gtk::prelude::*, gtk::pango,
+};
+
+/// Size of headings of level 1 to 6, relative to the text.
+const HEADING_SCALES: [f64; 6] = [2.0, 1.6, 1.35, 1.2, 1.1, 1.0];
+
+const MARKER: &str = "wysiwym-marker";
+const STRONG: &str = "wysiwym-strong";
+const EMPHASIS: &str = "wysiwym-emphasis";
+const STRIKETHROUGH: &str = "wysiwym-strikethrough";
+const LINK: &str = "wysiwym-link";
+
+lazy_static::lazy_static! {
+	static ref HEADING_PATTERN: regex::Regex = regex::Regex::new(r"^(#{1,6})[ \t]+").unwrap();
+	static ref STRONG_PATTERN: regex::Regex = regex::Regex::new(r"(\*\*|__)(\S(?:.*?\S)?)(\*\*|__)").unwrap();
+	static ref EMPHASIS_PATTERN: regex::Regex = regex::Regex::new(r"(\*|_)([^\s*_](?:[^*_]*?[^\s*_])?)(\*|_)").unwrap();
+	static ref STRIKETHROUGH_PATTERN: regex::Regex = regex::Regex::new(r"(~~)(\S(?:.*?\S)?)(~~)").unwrap();
+	static ref LINK_PATTERN: regex::Regex = regex::Regex::new(r"(!?\[)([^\]]*)(\]\([^)]*\))").unwrap();
+}
+
+fn heading_tag_name(level: usize) -> String {
+	format!("wysiwym-heading-{}", level)
+}
+
+fn tag(buffer: &gtk::TextBuffer, name: &str) -> gtk::TextTag {
+	let table = buffer.tag_table();
+	if let Some(tag) = table.lookup(name) {
+		return tag;
+	}
+	let builder = gtk::TextTag::builder().name(name);
+	let tag = match name {
+		MARKER => builder.invisible(true),
+		STRONG => builder.weight(700),
+		EMPHASIS => builder.style(pango::Style::Italic),
+		STRIKETHROUGH => builder.strikethrough(true),
+		LINK => builder.underline(pango::Underline::Single),
+		_ => {
+			let level = name.trim_start_matches("wysiwym-heading-").parse::<usize>().unwrap_or(6);
+			builder.weight(700).scale(HEADING_SCALES[level.clamp(1, 6) - 1])
+		},
+	}.build();
+	table.add(&tag);
+	tag
+}
+
+fn tag_names() -> Vec<String> {
+	let mut names: Vec<String> = [MARKER, STRONG, EMPHASIS, STRIKETHROUGH, LINK].iter().map(|name| name.to_string()).collect();
+	names.extend((1..=6).map(heading_tag_name));
+	names
+}
+
+/// Ranges, in characters, of the text and markers of each match of `pattern`,
+/// whose groups are the opening marker, the text and the closing marker.
+fn spans(pattern: &regex::Regex, line: &str) -> Vec<[Range<usize>; 3]> {
+	let chars = |byte: usize| line[..byte].chars().count();
+	pattern.captures_iter(line)
+		.filter_map(|captures| {
+			let (open, text, close) = (captures.get(1)?, captures.get(2)?, captures.get(3)?);
+			// `**` does not close `__`, nor `*` close `_`.
+			if open.as_str() != close.as_str() {
+				return None;
+			}
+			// Underscores within a word, as in snake_case, are not markers.
+			let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+			if open.as_str().starts_with('_')
+				&& (is_word_char(line[..open.start()].chars().last()) || is_word_char(line[close.end()..].chars().next())) {
+				return None;
+			}
+			Some([
+				chars(open.start())..chars(open.end()),
+				chars(text.start())..chars(text.end()),
+				chars(close.start())..chars(close.end()),
+			])
+		})
+		.collect()
+}
+
+/// Styles a line the way it reads, hiding its Markdown unless `reveal`.
+fn render_line(buffer: &gtk::TextBuffer, line: i32, reveal: bool) {
+	let Some(start) = buffer.iter_at_line(line) else {
+		return;
+	};
+	let mut end = start.clone();
+	if !end.ends_line() {
+		end.forward_to_line_end();
+	}
+	for name in tag_names() {
+		if let Some(tag) = buffer.tag_table().lookup(&name) {
+			buffer.remove_tag(&tag, &start, &end);
+		}
+	}
+	let text = buffer.text(&start, &end, false).to_string();
+	let offset = start.offset();
+	let apply = |name: &str, range: Range<usize>| {
+		if name == MARKER && reveal {
+			return;
+		}
+		buffer.apply_tag(
+			&tag(buffer, name),
+			&buffer.iter_at_offset(offset + range.start as i32),
+			&buffer.iter_at_offset(offset + range.end as i32),
+		);
+	};
+
+	if let Some(captures) = HEADING_PATTERN.captures(&text) {
+		let prefix = captures[0].chars().count();
+		apply(&heading_tag_name(captures[1].len()), 0..text.chars().count());
+		apply(MARKER, 0..prefix);
+	}
+
+	let spans_of = [
+		(LINK, spans(&LINK_PATTERN, &text)),
+		(STRIKETHROUGH, spans(&STRIKETHROUGH_PATTERN, &text)),
+		(STRONG, spans(&STRONG_PATTERN, &text)),
+		(EMPHASIS, spans(&EMPHASIS_PATTERN, &mask_strong(&text))),
+	];
+	for (name, spans) in spans_of {
+		for [open, inner, close] in spans {
+			apply(name, inner);
+			apply(MARKER, open);
+			apply(MARKER, close);
+		}
+	}
+}
+
+/// Blanks out the markers of strong emphasis, so they are not read again as
+/// those of emphasis. They are ASCII, so offsets in the line stay the same.
+fn mask_strong(line: &str) -> String {
+	let mut masked = line.to_string();
+	for captures in STRONG_PATTERN.captures_iter(line) {
+		let (Some(open), Some(close)) = (captures.get(1), captures.get(3)) else {
+			continue;
+		};
+		if open.as_str() == close.as_str() {
+			for marker in [open, close] {
+				masked.replace_range(marker.range(), &"\0".repeat(marker.len()));
+			}
+		}
+	}
+	masked
+}
+
+/// Renders `lines` of the buffer, showing the Markdown of `cursor_line`
+/// alone, as it is being edited.
+pub(super) fn render(buffer: &gtk::TextBuffer, lines: Range<i32>, cursor_line: i32) {
+	for line in lines {
+		render_line(buffer, line, line == cursor_line);
+	}
+}
+
+pub(super) fn render_all(buffer: &gtk::TextBuffer, cursor_line: i32) {
+	render(buffer, 0..buffer.line_count(), cursor_line);
+}
+
+/// Shows the text as written again.
+pub(super) fn clear(buffer: &gtk::TextBuffer) {
+	let (start, end) = buffer.bounds();
+	for name in tag_names() {
+		if let Some(tag) = buffer.tag_table().lookup(&name) {
+			buffer.remove_tag(&tag, &start, &end);
+		}
+	}
+}